@@ -0,0 +1,114 @@
+use crate::{PeekError, PutBackPeekMore, RefillPolicy};
+
+///A smart iterator like `PutBackPeekMore` that holds at most `CAP` values and reports an error instead of going past that.
+///Peeking too far or putting back into a full buffer never panics or discards a value, which suits embedded code without `alloc`.
+///The values are kept in a fixed-size ring like a `heapless::Deque`, without depending on the `heapless` crate.
+pub struct PutBackPeekMoreHeapless<Iter, const CAP: usize>
+where
+    Iter: Iterator,
+{
+    /// The wrapped iterator, which holds the buffer.
+    pub(crate) inner: PutBackPeekMore<Iter, CAP>,
+}
+
+impl<Iter, const CAP: usize> PutBackPeekMoreHeapless<Iter, CAP>
+where
+    Iter: Iterator,
+{
+    ///Create a new iterator.
+    ///It only reads the values that are needed, so every slot that wasn't peeked is free for putting back.
+    pub fn new(iter: Iter) -> Self {
+        Self {
+            inner: PutBackPeekMore::with_policy(iter, RefillPolicy::Minimal),
+        }
+    }
+
+    ///Look at the next value of the iterator without consuming it.
+    pub fn peek(&mut self) -> Option<&Iter::Item> {
+        self.inner.peek_checked()
+    }
+
+    ///Look at the value `n` places after the next value without consuming anything.
+    ///Returns `Err(PeekError::AmountTooLarge)` if `n + 1` is larger than `CAP`.
+    pub fn peek_nth(&mut self, n: usize) -> Result<Option<&Iter::Item>, PeekError> {
+        match self.inner.try_peek_nth(n) {
+            Some(slot) => Ok(slot.as_ref()),
+            None => Err(PeekError::AmountTooLarge),
+        }
+    }
+
+    ///Change the next consumed value of the iterator.
+    ///Returns the value back in `Err` if the buffer is full.
+    pub fn put_back(&mut self, val: Iter::Item) -> Result<(), Iter::Item> {
+        if self.inner.put_back_capacity() == 0 {
+            return Err(val);
+        }
+        self.inner.put_back(Some(val));
+        Ok(())
+    }
+}
+
+impl<Iter, const CAP: usize> core::fmt::Debug for PutBackPeekMoreHeapless<Iter, CAP>
+where
+    Iter: Iterator,
+    Iter::Item: core::fmt::Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("PutBackPeekMoreHeapless")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<Iter, const CAP: usize> Iterator for PutBackPeekMoreHeapless<Iter, CAP>
+where
+    Iter: Iterator,
+{
+    type Item = Iter::Item;
+    ///Consume the iterator.
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{PeekError, PutBackPeekMoreHeapless};
+
+    #[test]
+    fn test_peek_nth() {
+        let mut iter: PutBackPeekMoreHeapless<_, 3> = PutBackPeekMoreHeapless::new(0..10);
+        assert_eq!(iter.peek(), Some(&0));
+        assert_eq!(iter.peek_nth(2), Ok(Some(&2)));
+        assert_eq!(iter.peek_nth(3), Err(PeekError::AmountTooLarge));
+        assert_eq!(iter.next(), Some(0));
+    }
+
+    #[test]
+    fn test_put_back_full() {
+        let mut iter: PutBackPeekMoreHeapless<_, 3> = PutBackPeekMoreHeapless::new(0..10);
+        assert_eq!(iter.peek_nth(2), Ok(Some(&2)));
+        assert_eq!(iter.put_back(10), Err(10));
+        iter.next();
+        assert_eq!(iter.put_back(10), Ok(()));
+        assert_eq!(iter.put_back(11), Err(11));
+        assert!(iter.eq([10, 1, 2, 3, 4, 5, 6, 7, 8, 9]));
+    }
+
+    #[test]
+    fn test_put_back() {
+        let mut iter: PutBackPeekMoreHeapless<_, 3> = PutBackPeekMoreHeapless::new(0..10);
+        for _ in 0..4 {
+            iter.next();
+        }
+        assert_eq!(iter.put_back(3), Ok(()));
+        assert_eq!(iter.put_back(2), Ok(()));
+        assert_eq!(iter.put_back(1), Ok(()));
+        assert_eq!(iter.put_back(0), Err(0));
+        assert!(iter.eq(1..10));
+    }
+}
@@ -1,7 +1,67 @@
 #![no_std]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+mod positioned;
+pub use positioned::Positioned;
+
+#[cfg(feature = "heapless")]
+mod heapless;
+#[cfg(feature = "heapless")]
+pub use heapless::PutBackPeekMoreHeapless;
+
+#[cfg(feature = "alloc")]
+mod vec;
+#[cfg(feature = "alloc")]
+pub use vec::PutBackPeekMoreVec;
+
+///Decides how much of the underlying iterator is read when the buffer needs more values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RefillPolicy {
+    ///Fill the whole buffer every time more values are needed, for cheap iterators that benefit from reading in batches.
+    Eager,
+    ///Only read the values that are actually needed. This is what `PutBackPeekMore::new` uses.
+    #[default]
+    Minimal,
+}
+
+///The error returned when the buffer is too small for what was asked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PeekError {
+    ///The amount to peek at is larger than the buffer.
+    AmountTooLarge,
+    ///The buffer is full, so putting back would discard a buffered value.
+    PutBackFull,
+}
+
+///The error returned by `PutBackPeekMore::seek_relative`. Nothing is moved when seeking back fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeekError {
+    ///Seeking back went further than the values still in the buffer. Holds how far back it was possible to go.
+    NotBuffered(usize),
+    ///The iterator ended while seeking forward. Holds how many values were skipped before the end.
+    EndOfIterator(usize),
+}
+
+///A position in a `PutBackPeekMore`, returned by `checkpoint` and restored with `reset_to`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// The value of the structs `consumed` field when the checkpoint was taken.
+    pub(crate) consumed: usize,
+}
+
+///What `PutBackPeekMore::peek_state` found at the next position of the iterator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Peeked<T> {
+    ///There is a next value.
+    Item(T),
+    ///The iterator has ended.
+    Eof,
+}
+
 ///A smart iterator that lets you peek at more than x value of it and put back.
-/// Make sure to use a large enough `BUFSIZE` , otherwise you will read garbage data.
+/// Make sure to use a large enough `BUFSIZE`, peeking further than it panics.
 /// The minimum amount value of `BUFSIZE` should be *how much you are going to peek more* + 1
 pub struct PutBackPeekMore<Iter, const BUFSIZE: usize>
 where
@@ -9,71 +69,1014 @@ where
 {
     /// The iterator to consume.
     pub(crate) iter: Iter,
-    /// A buffer containing "peek" data. Note that reading this blindly will give you garbage data, as its allocated efficiently according to different calls.
+    /// A circular buffer containing "peek" data. The `len` slots starting at `head` hold the buffered values, wrapping around at `BUFSIZE`.
     pub(crate) peek: [Option<Iter::Item>; BUFSIZE],
-    /// A smart counter thats used to decide when allocations should be made in the peek field.
-    pub(crate) fizz: usize,
+    /// The index of the next value in the structs `peek` field.
+    pub(crate) head: usize,
+    /// How many slots starting at `head` are buffered. A `None` among them means the iterator ended there.
+    pub(crate) len: usize,
+    /// How many slots right before `head` still hold consumed values that `rewind` can go back to. The other free slots are always `None`.
+    pub(crate) retained: usize,
+    /// How many values were consumed or skipped minus how many were put back, wrapping around. A `None` is never counted. Checkpoints are taken from it.
+    pub(crate) consumed: usize,
+    /// How much of the iterator is read when the buffer needs more values.
+    pub(crate) policy: RefillPolicy,
+    /// Whether every refill also drops the retained values, so all free slots are `None`. Set by `new_guarded`.
+    pub(crate) guarded: bool,
 }
 
 impl<Iter, const BUFSIZE: usize> PutBackPeekMore<Iter, BUFSIZE>
 where
     Iter: Iterator,
 {
-    ///Create a new iterator.
-    pub fn new(mut iter: Iter) -> Self {
-        let peek: [Option<Iter::Item>; BUFSIZE] = [(); BUFSIZE].map(|_| iter.next());
+    ///Create a new iterator. Values are only read from `iter` when they are peeked at or consumed.
+    pub fn new(iter: Iter) -> Self {
+        Self::with_policy(iter, RefillPolicy::Minimal)
+    }
+
+    ///Create a new iterator that sets every slot outside of the buffered values to `None` after each refill, for debugging stale reads.
+    ///This drops the consumed values kept for `rewind` like `commit` does, so a rewind only works until the next refill.
+    pub fn new_guarded(iter: Iter) -> Self {
+        Self {
+            guarded: true,
+            ..Self::new(iter)
+        }
+    }
+
+    ///Create a new iterator that reads from `iter` according to `policy`.
+    ///Nothing is read from `iter` until a value is needed. With `RefillPolicy::Eager` the whole buffer is filled at that point.
+    pub fn with_policy(iter: Iter, policy: RefillPolicy) -> Self {
+        Self::with_empty_buffer(iter, [(); BUFSIZE].map(|_| None), policy)
+    }
+
+    ///Create a new iterator like `new`, but build the buffer with a plain copy instead of `array::map`.
+    ///This generates much less code for a large `BUFSIZE`, and gives exactly the same buffer as `new`.
+    pub fn new_fast(iter: Iter) -> Self
+    where
+        Iter::Item: Copy,
+    {
+        Self::with_empty_buffer(iter, [None; BUFSIZE], RefillPolicy::Minimal)
+    }
+
+    ///Builds the struct around `peek`, which has to be all `None`, without reading from `iter`.
+    pub(crate) fn with_empty_buffer(
+        iter: Iter,
+        peek: [Option<Iter::Item>; BUFSIZE],
+        policy: RefillPolicy,
+    ) -> Self {
         Self {
             iter,
             peek,
-            fizz: 0,
+            head: 0,
+            len: 0,
+            retained: 0,
+            consumed: 0,
+            policy,
+            guarded: false,
         }
     }
 
     ///Look at the next value of the iterator without consuming it.
+    ///See `peek_checked` for a version that returns `Option<&Item>`.
+    ///
+    ///The returned reference borrows the iterator, so it is only valid until the next call that takes `&mut self`.
+    ///Every such call recomputes its position in the buffer, so a peeked value can never be read from a stale slot:
+    ///```compile_fail,E0502
+    ///use putbackpeekmore::PutBackPeekMore;
+    ///
+    ///let mut iter: PutBackPeekMore<_, 3> = PutBackPeekMore::new(0..10);
+    ///let peeked = iter.peek();
+    ///iter.next();
+    ///assert_eq!(peeked, &Some(0));
+    ///```
     pub fn peek(&mut self) -> &Option<Iter::Item> {
         self.demand(1);
-        &self.peek[self.fizz]
+        &self.peek[self.head]
     }
 
     ///Look at the next `amount` values of the iterator without consuming it.
     pub fn peek_value(&mut self, amount: usize) -> &[Option<Iter::Item>] {
         self.demand(amount);
-        &self.peek[self.fizz..self.fizz + amount]
+        self.window(amount)
     }
 
     ///Tells the struct to allocate data in the peek field according to the ``val`` parameter.
+    ///The `len` values from `head` are already read, so the iterator is only read when `val` goes past `len`.
+    ///Buffered values are never moved or discarded, only consumed values kept for `rewind` can be written over.
     pub(crate) fn demand(&mut self, val: usize) {
-        if self.fizz + val > self.peek.len() {
-            self.write_over_start();
+        debug_assert!(
+            self.len + self.retained <= BUFSIZE,
+            "the buffer holds more than BUFSIZE values"
+        );
+        if val > self.len {
+            let target = match self.policy {
+                RefillPolicy::Eager => BUFSIZE,
+                RefillPolicy::Minimal => val.min(BUFSIZE),
+            };
+            for offset in self.len..target {
+                let index = self.index(offset);
+                self.peek[index] = self.iter.next();
+            }
+            self.len = target;
+            self.retained = self.retained.min(BUFSIZE - self.len);
+            if self.guarded {
+                self.commit();
+            }
         }
     }
 
-    ///Replaces every value at the structs `peek` field with the consumed values of the structs `iter` field.
-    pub(crate) fn write_over_val(&mut self, val: usize) {
-        for i in val..(BUFSIZE - self.fizz) {
-            self.peek[i] = self.peek[self.fizz + i].take();
-        }
-        for i in (BUFSIZE - self.fizz)..BUFSIZE {
-            self.peek[i] = self.iter.next();
-        }
-        self.fizz = 0;
+    ///Returns the index in the structs `peek` field of the value `offset` places after the next value.
+    pub(crate) fn index(&self, offset: usize) -> usize {
+        (self.head + offset) % BUFSIZE
+    }
+
+    ///Returns the first `amount` buffered slots as one slice, rotating the buffer first if they wrap around its end.
+    pub(crate) fn window(&mut self, amount: usize) -> &[Option<Iter::Item>] {
+        self.window_mut(amount)
     }
 
-    ///Replaces every value after the structs `peek` field after the `val` parameter with the consumed values of the structs `iter` field.
-    pub(crate) fn write_over_start(&mut self) {
-        self.write_over_val(0);
+    ///Like `window`, but returns a mutable slice.
+    pub(crate) fn window_mut(&mut self, amount: usize) -> &mut [Option<Iter::Item>] {
+        if self.head + amount > BUFSIZE {
+            self.peek.rotate_left(self.head);
+            self.head = 0;
+        }
+        &mut self.peek[self.head..self.head + amount]
     }
 
     ///Change the next consumed value of the iterator.
+    ///The buffer only fills up when `BUFSIZE` values are peeked at or put back.
+    ///
+    ///# Panics
+    ///Panics if the buffer is full, since a buffered value would have to be discarded. Use `try_put_back` to get an error instead.
     pub fn put_back(&mut self, val: Option<Iter::Item>) {
-        if self.fizz > 0 {
-            self.peek[self.fizz - 1] = val;
-            self.fizz -= 1;
+        assert!(self.len < BUFSIZE, "cannot put back into a full buffer");
+        self.head = self.index(BUFSIZE - 1);
+        self.consumed = self.consumed.wrapping_sub(usize::from(val.is_some()));
+        self.peek[self.head] = val;
+        self.len += 1;
+        self.retained = self.retained.saturating_sub(1);
+    }
+
+    ///Move back one position, so the previously consumed value becomes the next value again.
+    ///`next` hands the value over, so only values consumed with `next_retained` or skipped with `seek_relative` are still in the buffer to rewind to.
+    ///Returns `Err(())` if the previous value is not in the buffer anymore.
+    #[allow(clippy::result_unit_err)]
+    pub fn rewind(&mut self) -> Result<(), ()> {
+        self.seek_relative(-1).map_err(|_| ())
+    }
+
+    ///Move back one position like `rewind`, and change the value that becomes the next value again with `f`.
+    ///Returns `Err(())` without calling `f` if the previous value is not in the buffer anymore.
+    #[allow(clippy::result_unit_err)]
+    pub fn modify_last<F: FnOnce(&mut Iter::Item)>(&mut self, f: F) -> Result<(), ()> {
+        self.rewind()?;
+        if let Some(item) = &mut self.peek[self.head] {
+            f(item);
+        }
+        Ok(())
+    }
+
+    ///Consume the iterator like `next`, but keep a copy of the value in the buffer so `rewind` can go back to it.
+    ///The copy is kept until a refill or a put back value needs its slot.
+    pub fn next_retained(&mut self) -> Option<Iter::Item>
+    where
+        Iter::Item: Clone,
+    {
+        self.demand(1);
+        let out = self.peek[self.head].clone();
+        self.advance();
+        self.retained += 1;
+        self.consumed = self.consumed.wrapping_add(usize::from(out.is_some()));
+        out
+    }
+
+    ///Moves `head` past the next slot, which has to be buffered. The slot is not cleared, and `consumed` is left to the caller.
+    pub(crate) fn advance(&mut self) {
+        self.head = self.index(1);
+        self.len -= 1;
+    }
+
+    ///Replace the underlying iterator with `new`, keeping the values that are already buffered.
+    ///The buffered values are yielded before the values of `new`, and anything after the end of the old iterator is discarded.
+    pub fn replace_source<J: Iterator<Item = Iter::Item>>(
+        self,
+        new: J,
+    ) -> PutBackPeekMore<J, BUFSIZE> {
+        self.map_source(|_| new)
+    }
+
+    ///Wrap the underlying iterator in `core::iter::StepBy`, so only every `step`th value is yielded.
+    ///The values that are already buffered are stepped over the same way, so this can be called after peeking.
+    ///
+    ///# Panics
+    ///Panics if `step` is 0.
+    pub fn step_by(mut self, step: usize) -> PutBackPeekMore<core::iter::StepBy<Iter>, BUFSIZE> {
+        assert!(step != 0, "step must be non-zero");
+        let live = self.buffered_len();
+        for offset in 0..live {
+            let slot = self.peek[self.index(offset)].take();
+            if offset % step == 0 {
+                self.peek[self.index(offset / step)] = slot;
+            }
+        }
+        let skip = if live == self.len {
+            (step - live % step) % step
+        } else {
+            0
+        };
+        self.map_source(|mut iter| {
+            for _ in 0..skip {
+                iter.next();
+            }
+            iter.step_by(step)
+        })
+    }
+
+    ///Builds a new struct around the iterator returned by `f`, keeping the buffered values up to the first `None`.
+    ///The rest of the buffer is read from the new iterator when it is needed. Values kept for `rewind` are dropped, since they belong to the old iterator.
+    pub(crate) fn map_source<J: Iterator<Item = Iter::Item>>(
+        mut self,
+        f: impl FnOnce(Iter) -> J,
+    ) -> PutBackPeekMore<J, BUFSIZE> {
+        let live = self.buffered_len();
+        for offset in live..self.len {
+            self.peek[self.index(offset)] = None;
+        }
+        self.commit();
+        let Self {
+            iter,
+            peek,
+            head,
+            consumed,
+            policy,
+            guarded,
+            ..
+        } = self;
+        PutBackPeekMore {
+            iter: f(iter),
+            peek,
+            head,
+            len: live,
+            retained: 0,
+            consumed,
+            policy,
+            guarded,
+        }
+    }
+
+    ///Look at the next value of the iterator without consuming it, returning `None` only at the end of the iterator.
+    ///This matches `core::iter::Peekable::peek`, and is the recommended way to peek.
+    pub fn peek_checked(&mut self) -> Option<&Iter::Item> {
+        self.demand(1);
+        self.peek[self.head].as_ref()
+    }
+
+    ///Consume the rest of the iterator into a `Vec`.
+    ///The buffered values come first, including any value that was put back, followed by the rest of the underlying iterator.
+    #[cfg(feature = "alloc")]
+    pub fn into_vec(self) -> alloc::vec::Vec<Iter::Item> {
+        self.collect()
+    }
+
+    ///Apply `f` to the next value of the iterator without consuming it, returning `None` at the end of the iterator.
+    pub fn peek_map<B>(&mut self, f: impl FnOnce(&Iter::Item) -> B) -> Option<B> {
+        self.peek_checked().map(f)
+    }
+
+    ///Returns how many values are currently buffered, starting with the next value. This never reads from the underlying iterator.
+    ///The buffer holds at most `BUFSIZE` values, so the `peek_*` methods that look at all the buffered values never see more than that.
+    pub fn buffered_len(&self) -> usize {
+        (0..self.len)
+            .take_while(|&offset| self.peek[self.index(offset)].is_some())
+            .count()
+    }
+
+    ///Fills the buffer as much as possible and returns the buffered values, all of which are `Some`. There are at most `BUFSIZE` of them.
+    pub(crate) fn buffered(&mut self) -> &[Option<Iter::Item>] {
+        self.demand(BUFSIZE);
+        let len = self.buffered_len();
+        self.window(len)
+    }
+
+    ///Returns the offset of the first buffered value that doesn't satisfy `pred`, assuming the buffered values are partitioned by it.
+    pub fn peek_partition_point(&mut self, pred: impl Fn(&Iter::Item) -> bool) -> usize {
+        self.buffered()
+            .partition_point(|slot| slot.as_ref().is_some_and(&pred))
+    }
+
+    ///Wrap the underlying iterator in `core::iter::Take`, so at most `n` more values are yielded.
+    ///`n` counts the values that are already buffered, so this can be called after peeking. Buffered values past `n` are discarded.
+    pub fn take(mut self, n: usize) -> PutBackPeekMore<core::iter::Take<Iter>, BUFSIZE> {
+        let live = self.buffered_len();
+        for offset in n.min(live)..self.len {
+            self.peek[self.index(offset)] = None;
+        }
+        self.map_source(|iter| iter.take(n.saturating_sub(live)))
+    }
+
+    ///Returns how many of the buffered values would be left after removing consecutive values that are equal according to `eq`, like `slice::dedup_by`.
+    pub fn peek_distinct_run(&mut self, eq: impl Fn(&Iter::Item, &Iter::Item) -> bool) -> usize {
+        let buffered = self.buffered();
+        let distinct = buffered
+            .windows(2)
+            .filter(|pair| match pair {
+                [Some(a), Some(b)] => !eq(a, b),
+                _ => false,
+            })
+            .count();
+        distinct + usize::from(!buffered.is_empty())
+    }
+
+    ///Look at the next value of the iterator without consuming it, or `default` at the end of the iterator.
+    pub fn peek_or<'a>(&'a mut self, default: &'a Iter::Item) -> &'a Iter::Item {
+        self.peek_checked().unwrap_or(default)
+    }
+
+    ///Look at the next value of the iterator without consuming it, or the value returned by `default` at the end of the iterator.
+    ///`default` is only called at the end of the iterator.
+    pub fn peek_or_else<'a>(
+        &'a mut self,
+        default: impl FnOnce() -> &'a Iter::Item,
+    ) -> &'a Iter::Item {
+        self.peek_checked().unwrap_or_else(default)
+    }
+
+    ///Splits the struct into its buffered values and the structs `iter` field.
+    ///The iterator is `None` when the buffer already reached its end, so nothing after it should be read.
+    pub(crate) fn into_split(mut self) -> (impl Iterator<Item = Iter::Item>, Option<Iter>) {
+        let ended = self.buffered_len() < self.len;
+        self.peek.rotate_left(self.head);
+        let Self {
+            iter, peek, len, ..
+        } = self;
+        let buffered = peek.into_iter().take(len).map_while(|slot| slot);
+        (buffered, (!ended).then_some(iter))
+    }
+
+    ///Returns whether there is a next value and it satisfies `pred`, without consuming it.
+    ///`pred` is not called at the end of the iterator.
+    pub fn peek_is(&mut self, pred: impl FnOnce(&Iter::Item) -> bool) -> bool {
+        self.peek_checked().is_some_and(pred)
+    }
+
+    ///Iterate over the buffered values together with their offset from the next value, without consuming them.
+    pub fn peek_enumerated(&mut self) -> impl Iterator<Item = (usize, &Iter::Item)> {
+        self.buffered().iter().flatten().enumerate()
+    }
+
+    ///Consume exactly `n` values, or none at all if the iterator ends first.
+    ///When there are fewer than `n` values left, returns `Err` with how many there were, and leaves them all in place.
+    ///
+    ///# Panics
+    ///Panics if `n` is larger than `BUFSIZE`, since the values have to be peeked before consuming them. Use `try_consume_exact` to check for that instead.
+    pub fn consume_exact(&mut self, n: usize) -> Result<(), usize> {
+        self.try_consume_exact(n)
+            .expect("cannot consume more than BUFSIZE values at once")
+    }
+
+    ///Like `consume_exact`, but returns `None` without consuming anything instead of panicking when `n` is larger than `BUFSIZE`.
+    pub fn try_consume_exact(&mut self, n: usize) -> Option<Result<(), usize>> {
+        if n > BUFSIZE {
+            return None;
+        }
+        self.demand(n);
+        let available = self.buffered_len().min(n);
+        if available < n {
+            return Some(Err(available));
+        }
+        for _ in 0..n {
+            self.next();
+        }
+        Some(Ok(()))
+    }
+
+    ///Returns whether the iterator has no values left. This reads the next value if it isn't buffered yet.
+    ///This isn't called `is_empty` so it doesn't collide with the unstable `ExactSizeIterator::is_empty`.
+    pub fn is_exhausted(&mut self) -> bool {
+        self.peek_checked().is_none()
+    }
+
+    ///Look at the value `n` places after the next value without consuming anything, so `peek_nth(0)` is the same as `peek`.
+    ///
+    ///# Panics
+    ///Panics if `n + 1` is larger than `BUFSIZE`.
+    pub fn peek_nth(&mut self, n: usize) -> &Option<Iter::Item> {
+        assert!(n < BUFSIZE, "cannot peek further than BUFSIZE");
+        self.demand(n + 1);
+        &self.peek[self.index(n)]
+    }
+
+    ///Like `peek_nth`, but returns `None` instead of panicking when `n + 1` is larger than `BUFSIZE`.
+    pub fn try_peek_nth(&mut self, n: usize) -> Option<&Option<Iter::Item>> {
+        if n < BUFSIZE {
+            Some(self.peek_nth(n))
+        } else {
+            None
+        }
+    }
+
+    ///Compare the next value of the iterator with `other` without consuming it, returning `None` at the end of the iterator.
+    pub fn peek_cmp(&mut self, other: &Iter::Item) -> Option<core::cmp::Ordering>
+    where
+        Iter::Item: Ord,
+    {
+        self.peek_map(|item| item.cmp(other))
+    }
+
+    ///Consume values until the next one satisfies `pred`, and return how many were consumed.
+    ///The value that satisfies `pred` is left as the next value. This reads as far into the iterator as needed.
+    pub fn consume_until_pred(&mut self, pred: impl Fn(&Iter::Item) -> bool) -> usize {
+        let mut consumed = 0;
+        while self.peek_is(|item| !pred(item)) {
+            self.next();
+            consumed += 1;
+        }
+        consumed
+    }
+
+    ///Look at the next `amount` values of the iterator without consuming it, stopping early at the end of the iterator.
+    #[cfg(feature = "alloc")]
+    pub fn peek_prefix(&mut self, amount: usize) -> alloc::vec::Vec<&Iter::Item> {
+        self.peek_value(amount)
+            .iter()
+            .map_while(Option::as_ref)
+            .collect()
+    }
+
+    ///Returns how many values came before the next value, counting from the start of the stream: the consumed or skipped values minus the ones put back.
+    ///Putting back more values than were consumed makes it wrap around. This is the count `checkpoint` saves.
+    ///This isn't called `position` so it doesn't hide `Iterator::position`.
+    pub fn stream_position(&self) -> usize {
+        self.consumed
+    }
+
+    ///Look at the next two values of the iterator without consuming them, returning `None` if there are fewer than two left.
+    ///
+    ///# Panics
+    ///Panics if `BUFSIZE` is smaller than 2.
+    pub fn peek_pair_checked(&mut self) -> Option<(&Iter::Item, &Iter::Item)> {
+        match self.peek_value(2) {
+            [Some(first), Some(second)] => Some((first, second)),
+            _ => None,
+        }
+    }
+
+    ///Copy the next `W` values into an array and consume only the first of them, so the next call returns an overlapping window.
+    ///Returns `None` without consuming anything if there are fewer than `W` values left.
+    ///
+    ///# Panics
+    ///Panics if `W` is 0 or larger than `BUFSIZE`.
+    pub fn advance_window<const W: usize>(&mut self) -> Option<[Iter::Item; W]>
+    where
+        Iter::Item: Clone,
+    {
+        assert!(
+            W > 0 && W <= BUFSIZE,
+            "window has to hold between 1 and BUFSIZE values"
+        );
+        self.demand(W);
+        if self.buffered_len() < W {
+            return None;
+        }
+        let window = core::array::from_fn(|i| {
+            self.peek[self.index(i)]
+                .clone()
+                .expect("window is buffered")
+        });
+        self.next();
+        Some(window)
+    }
+
+    ///Get a mutable reference to the value `n` places after the next value without consuming anything, or `None` if the iterator ends first.
+    ///Changes to the value are kept when it is consumed.
+    ///
+    ///# Panics
+    ///Panics if `n + 1` is larger than `BUFSIZE`.
+    pub fn peek_nth_mut(&mut self, n: usize) -> Option<&mut Iter::Item> {
+        assert!(n < BUFSIZE, "cannot peek further than BUFSIZE");
+        self.demand(n + 1);
+        let index = self.index(n);
+        self.peek[index].as_mut()
+    }
+
+    ///Copy up to `K` of the next values into an array without consuming them, and return it with how many values were copied.
+    ///The slots after the end of the iterator are left as `Default::default()`.
+    ///
+    ///# Panics
+    ///Panics if `K` is larger than `BUFSIZE`.
+    pub fn peek_collect<const K: usize>(&mut self) -> ([Iter::Item; K], usize)
+    where
+        Iter::Item: Clone + Default,
+    {
+        assert!(K <= BUFSIZE, "cannot peek further than BUFSIZE");
+        self.demand(K);
+        let count = self.buffered_len().min(K);
+        let values = core::array::from_fn(|i| self.peek[self.index(i)].clone().unwrap_or_default());
+        (values, count)
+    }
+
+    ///Throw away everything in the buffer, including values that were put back, and start over from the current position of the underlying iterator.
+    ///The buffered values are lost, so the next value is the first one the underlying iterator hasn't given yet.
+    pub fn clear_and_refill(&mut self) {
+        self.discard_buffered();
+        for slot in &mut self.peek {
+            *slot = None;
+        }
+        self.head = 0;
+        self.len = 0;
+        self.retained = 0;
+        if self.policy == RefillPolicy::Eager {
+            self.demand(BUFSIZE);
+        }
+    }
+
+    ///Sum the buffered values without consuming them.
+    pub fn peek_sum<S: core::iter::Sum<Iter::Item>>(&mut self) -> S
+    where
+        Iter::Item: Clone,
+    {
+        self.buffered().iter().flatten().cloned().sum()
+    }
+
+    ///Multiply the buffered values without consuming them.
+    pub fn peek_product<P: core::iter::Product<Iter::Item>>(&mut self) -> P
+    where
+        Iter::Item: Clone,
+    {
+        self.buffered().iter().flatten().cloned().product()
+    }
+
+    ///Returns the size of the buffer, which is `BUFSIZE`. The furthest you can peek is `capacity() - 1` values after the next value.
+    pub const fn capacity(&self) -> usize {
+        BUFSIZE
+    }
+
+    ///Apply `f` to the buffered values without consuming them, collecting the results until `f` returns `None`.
+    #[cfg(feature = "alloc")]
+    pub fn peek_map_while<B>(
+        &mut self,
+        f: impl Fn(&Iter::Item) -> Option<B>,
+    ) -> alloc::vec::Vec<B> {
+        self.buffered().iter().flatten().map_while(f).collect()
+    }
+
+    ///Returns the offset of the last buffered value that satisfies `pred`, searching from the end of the buffer.
+    pub fn peek_rfind(&mut self, pred: impl Fn(&Iter::Item) -> bool) -> Option<usize> {
+        self.buffered()
+            .iter()
+            .rposition(|slot| slot.as_ref().is_some_and(&pred))
+    }
+
+    ///Insert `items` after the buffered values, so they come before the values that haven't been read from the underlying iterator yet.
+    ///Unlike `put_back`, which changes the next value, this keeps the buffered values first.
+    ///The items go before the `None` slots at the end of the buffer, which mark the end of the iterator, and those slots are reused once the buffer is full.
+    ///Every other buffered value, including a `None` that was put back, is kept.
+    ///Returns how many values were inserted; the rest of `items` is not read once the buffer is full.
+    pub fn prime<I: IntoIterator<Item = Iter::Item>>(&mut self, items: I) -> usize {
+        self.compact();
+        let mut end = self.len;
+        while end > 0 && self.peek[end - 1].is_none() {
+            end -= 1;
+        }
+        let mut items = items.into_iter();
+        let mut inserted = 0;
+        while self.len < BUFSIZE || self.len - inserted > end {
+            let Some(item) = items.next() else {
+                break;
+            };
+            if self.len == BUFSIZE {
+                self.peek[end..].rotate_left(1);
+                self.len -= 1;
+            }
+            self.peek[self.len] = Some(item);
+            self.len += 1;
+            inserted += 1;
+        }
+        self.peek[end..self.len].rotate_right(inserted);
+        inserted
+    }
+
+    ///Look at `len` values starting `start` places after the next value, without consuming anything.
+    ///
+    ///# Panics
+    ///Panics if `start + len` is larger than `BUFSIZE`.
+    pub fn peek_range(&mut self, start: usize, len: usize) -> &[Option<Iter::Item>] {
+        assert!(start + len <= BUFSIZE, "cannot peek further than BUFSIZE");
+        self.demand(start + len);
+        &self.window(start + len)[start..]
+    }
+
+    ///Throw away the buffered values without reading anything from the underlying iterator, and return how many were thrown away.
+    ///The next value is then read from the underlying iterator.
+    pub fn discard_buffered(&mut self) -> usize {
+        let discarded = (0..self.len)
+            .filter_map(|offset| self.peek[self.index(offset)].take())
+            .count();
+        self.len = 0;
+        self.consumed = self.consumed.wrapping_add(discarded);
+        discarded
+    }
+
+    ///Consume and return the values while they satisfy `pred`. The first value that doesn't is left as the next value.
+    ///This reads as far into the iterator as needed.
+    #[cfg(feature = "alloc")]
+    pub fn consume_while(
+        &mut self,
+        pred: impl Fn(&Iter::Item) -> bool,
+    ) -> alloc::vec::Vec<Iter::Item> {
+        let mut out = alloc::vec::Vec::new();
+        while self.peek_is(&pred) {
+            out.extend(self.next());
+        }
+        out
+    }
+
+    ///Move the position of the iterator by `delta` values.
+    ///Seeking forward skips values, reading from the underlying iterator if needed, and keeps the skipped values in the buffer while they fit.
+    ///Seeking back only works over values still in the buffer, like `rewind`.
+    pub fn seek_relative(&mut self, delta: isize) -> Result<(), SeekError> {
+        let steps = delta.unsigned_abs();
+        if delta < 0 {
+            let available = (1..=self.retained)
+                .take_while(|&back| self.peek[self.index(BUFSIZE - back)].is_some())
+                .count();
+            if available < steps {
+                return Err(SeekError::NotBuffered(available));
+            }
+            self.head = self.index(BUFSIZE - steps);
+            self.len += steps;
+            self.retained -= steps;
+            self.consumed = self.consumed.wrapping_sub(steps);
+        } else {
+            for skipped in 0..steps {
+                self.demand(1);
+                if self.peek[self.head].is_none() {
+                    return Err(SeekError::EndOfIterator(skipped));
+                }
+                self.advance();
+                self.retained += 1;
+                self.consumed = self.consumed.wrapping_add(1);
+            }
+        }
+        Ok(())
+    }
+
+    ///Look at the next value of the iterator without consuming it, as a `Peeked` that reads well in `match` arms.
+    pub fn peek_state(&mut self) -> Peeked<&Iter::Item> {
+        self.demand(1);
+        match &self.peek[self.head] {
+            Some(item) => Peeked::Item(item),
+            None => Peeked::Eof,
+        }
+    }
+
+    ///Returns how many values can be put back before `put_back` panics.
+    pub fn put_back_capacity(&self) -> usize {
+        BUFSIZE - self.len
+    }
+
+    ///Move the buffered values to the start of the buffer without reading from the underlying iterator.
+    ///This drops the consumed values kept for `rewind`, so refills and put backs never have to write over them.
+    pub fn compact(&mut self) {
+        self.peek.rotate_left(self.head);
+        self.head = 0;
+        for slot in &mut self.peek[self.len..] {
+            *slot = None;
+        }
+        self.retained = 0;
+    }
+
+    ///Consume values until the next one satisfies `pred`, and look at it without consuming it.
+    ///Returns `None` if the iterator ends first. This reads as far into the iterator as needed.
+    pub fn find_peek(&mut self, pred: impl Fn(&Iter::Item) -> bool) -> Option<&Iter::Item> {
+        self.consume_until_pred(pred);
+        self.peek_checked()
+    }
+
+    ///Look at the next `amount` values of the iterator without consuming it, split into the next value and the ones after it.
+    ///Returns `None` at the end of the iterator, or if `amount` is 0.
+    #[allow(clippy::type_complexity)]
+    pub fn split_peek(&mut self, amount: usize) -> Option<(&Iter::Item, &[Option<Iter::Item>])> {
+        match self.peek_value(amount).split_first() {
+            Some((Some(head), rest)) => Some((head, rest)),
+            _ => None,
+        }
+    }
+
+    ///Run `f` with the iterator and return its result.
+    ///Values that `f` peeked but didn't consume stay buffered afterwards, including when `f` builds adaptors with `by_ref`,
+    ///since those only ever call `next` on the iterator and never skip the buffer.
+    pub fn scoped<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
+        f(self)
+    }
+
+    ///Returns how many of the buffered values, starting with the next value, share the same `key` as the next value.
+    pub fn peek_run_end<K: PartialEq>(&mut self, key: impl Fn(&Iter::Item) -> K) -> usize {
+        let mut values = self.buffered().iter().flatten();
+        match values.next() {
+            Some(first) => {
+                let first = key(first);
+                1 + values.take_while(|item| key(item) == first).count()
+            }
+            None => 0,
+        }
+    }
+
+    ///Returns whether the next value is equal to any value in `set`, without consuming it.
+    pub fn peek_in(&mut self, set: &[Iter::Item]) -> bool
+    where
+        Iter::Item: PartialEq,
+    {
+        self.peek_is(|item| set.contains(item))
+    }
+
+    ///Consume and return the next value if it is equal to any value in `set`.
+    pub fn consume_in(&mut self, set: &[Iter::Item]) -> Option<Iter::Item>
+    where
+        Iter::Item: PartialEq,
+    {
+        if self.peek_in(set) { self.next() } else { None }
+    }
+
+    ///Consume every remaining value, calling `f` on each one before it is dropped.
+    ///Unlike `for_each`, this only borrows the iterator, so it can still be used afterwards.
+    pub fn drain_with(&mut self, mut f: impl FnMut(&Iter::Item)) {
+        for item in self.by_ref() {
+            f(&item);
+        }
+    }
+
+    ///Look at the value `n` places after the next value without consuming anything, or `default` if the iterator ends before it.
+    ///
+    ///# Panics
+    ///Panics if `n + 1` is larger than `BUFSIZE`.
+    pub fn peek_nth_or<'a>(&'a mut self, n: usize, default: &'a Iter::Item) -> &'a Iter::Item {
+        self.peek_nth(n).as_ref().unwrap_or(default)
+    }
+
+    ///Look at the leading values that satisfy `pred` without consuming them, but never more than `max` of them.
+    ///The look-ahead is also limited to `BUFSIZE`, so this never discards a buffered value.
+    pub fn peek_while_bounded(
+        &mut self,
+        max: usize,
+        pred: impl Fn(&Iter::Item) -> bool,
+    ) -> &[Option<Iter::Item>] {
+        let limit = max.min(BUFSIZE);
+        self.demand(limit);
+        let window = self.window(limit);
+        let len = window
+            .iter()
+            .take_while(|slot| slot.as_ref().is_some_and(&pred))
+            .count();
+        &window[..len]
+    }
+
+    ///Bring the buffer into its canonical layout without changing the values the iterator yields.
+    ///Afterwards the next value is at the start of the buffer, followed by the other buffered values, and every other slot is empty.
+    ///This is the same as `compact`, so values kept for `rewind` are dropped as well.
+    pub fn normalize(&mut self) {
+        self.compact();
+        debug_assert!(self.peek[self.len..].iter().all(Option::is_none));
+    }
+
+    ///Get a mutable reference to the next value without consuming it, inserting `default` as the next value at the end of the iterator.
+    ///`default` is dropped if there already is a next value.
+    pub fn next_mut_or(&mut self, default: Iter::Item) -> &mut Iter::Item {
+        self.demand(1);
+        self.peek[self.head].get_or_insert(default)
+    }
+
+    ///Like `peek_value`, but returns `Err(PeekError::AmountTooLarge)` instead of panicking when `amount` is larger than `BUFSIZE`.
+    pub fn try_peek(&mut self, amount: usize) -> Result<&[Option<Iter::Item>], PeekError> {
+        if amount > BUFSIZE {
+            return Err(PeekError::AmountTooLarge);
+        }
+        Ok(self.peek_value(amount))
+    }
+
+    ///Like `put_back`, but returns `Err(PeekError::PutBackFull)` instead of panicking when the buffer is full.
+    ///`val` is dropped when putting back fails.
+    pub fn try_put_back(&mut self, val: Option<Iter::Item>) -> Result<(), PeekError> {
+        if self.put_back_capacity() == 0 {
+            return Err(PeekError::PutBackFull);
+        }
+        self.put_back(val);
+        Ok(())
+    }
+
+    ///Put back several values at once, so the next values are `items` in order, followed by the values that were next before.
+    ///Returns `Err(PeekError::PutBackFull)` if there are more items than `remaining_putback_capacity`, and puts nothing back in that case.
+    ///Like `compact`, this drops the consumed values kept for `rewind`.
+    pub fn put_back_n(
+        &mut self,
+        items: impl IntoIterator<Item = Iter::Item>,
+    ) -> Result<(), PeekError> {
+        self.compact();
+        let mut count = 0;
+        for item in items {
+            if self.len + count == BUFSIZE {
+                for slot in &mut self.peek[self.len..] {
+                    *slot = None;
+                }
+                return Err(PeekError::PutBackFull);
+            }
+            self.peek[self.len + count] = Some(item);
+            count += 1;
+        }
+        self.peek[..self.len + count].rotate_right(count);
+        self.len += count;
+        self.consumed = self.consumed.wrapping_sub(count);
+        Ok(())
+    }
+
+    ///Returns how many values can be put back without discarding a buffered value, which is also the most `put_back_n` accepts.
+    ///This is the same as `put_back_capacity`.
+    pub fn remaining_putback_capacity(&self) -> usize {
+        self.put_back_capacity()
+    }
+
+    ///Remember the current position, so `reset_to` can go back to it after speculatively consuming values.
+    ///Consume with `advance_cursor` or `next_retained` after taking it, since `next` hands the values over and they can't be gone back to.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            consumed: self.consumed,
+        }
+    }
+
+    ///Go back, or forward, to the position of `checkpoint`, like `seek_relative`.
+    ///Returns `Err(SeekError::NotBuffered)` without moving if the values since the checkpoint are not in the buffer anymore.
+    pub fn reset_to(&mut self, checkpoint: Checkpoint) -> Result<(), SeekError> {
+        self.seek_relative(checkpoint.consumed.wrapping_sub(self.consumed) as isize)
+    }
+
+    ///Move past the next value and return a reference to it, keeping it in the buffer so `reset_to` or `rewind` can go back to it.
+    ///Returns `None` without moving at the end of the iterator.
+    pub fn advance_cursor(&mut self) -> Option<&Iter::Item> {
+        self.seek_relative(1).ok()?;
+        self.peek[self.index(BUFSIZE - 1)].as_ref()
+    }
+
+    ///Drop the values kept for going back, so every checkpoint taken before now can only be reset to by seeking forward.
+    ///This frees their slots for refills and put backs.
+    pub fn commit(&mut self) {
+        for back in 1..=self.retained {
+            let index = self.index(BUFSIZE - back);
+            self.peek[index] = None;
+        }
+        self.retained = 0;
+    }
+
+    ///Consume and return the next value if it satisfies `func`, like `core::iter::Peekable::next_if`.
+    ///Otherwise nothing is consumed and `None` is returned.
+    pub fn next_if(&mut self, func: impl FnOnce(&Iter::Item) -> bool) -> Option<Iter::Item> {
+        if self.peek_is(func) {
+            self.next()
         } else {
-            self.write_over_val(1);
-            self.put_back(val);
-            self.fizz -= 1;
+            None
+        }
+    }
+
+    ///Consume and return the next value if it is equal to `expected`, like `core::iter::Peekable::next_if_eq`.
+    pub fn next_if_eq<T>(&mut self, expected: &T) -> Option<Iter::Item>
+    where
+        T: ?Sized,
+        Iter::Item: PartialEq<T>,
+    {
+        self.next_if(|item| item == expected)
+    }
+
+    ///Get a mutable reference to the next slot of the iterator without consuming it.
+    ///Setting it to `None` makes the iterator end there like `put_back(None)`, and setting it to `Some` at the end of the iterator adds a value.
+    pub fn peek_mut(&mut self) -> &mut Option<Iter::Item> {
+        self.demand(1);
+        &mut self.peek[self.head]
+    }
+
+    ///Get mutable references to the next `amount` slots of the iterator without consuming them.
+    ///Changes are kept when the values are consumed, and a `None` makes the iterator end there like `peek_mut`.
+    ///
+    ///# Panics
+    ///Panics if `amount` is larger than `BUFSIZE`.
+    pub fn peek_value_mut(&mut self, amount: usize) -> &mut [Option<Iter::Item>] {
+        self.demand(amount);
+        self.window_mut(amount)
+    }
+
+    ///Splits the struct into the buffered values, including values that were put back, and the underlying iterator.
+    ///Yielding the buffered values and then the iterator gives the same values as this struct would have.
+    pub fn into_parts(mut self) -> (impl Iterator<Item = Iter::Item>, Iter) {
+        self.peek.rotate_left(self.head);
+        let Self {
+            iter, peek, len, ..
+        } = self;
+        (peek.into_iter().take(len).flatten(), iter)
+    }
+
+    ///Returns the underlying iterator, or gives the struct back in `Err` if there still are buffered values that would be lost.
+    ///Use `into_parts` to keep the buffered values instead.
+    pub fn into_inner(self) -> Result<Iter, Self> {
+        if (0..self.len).any(|offset| self.peek[self.index(offset)].is_some()) {
+            return Err(self);
+        }
+        Ok(self.iter)
+    }
+
+    ///Iterate over the buffered values without consuming them, filling the buffer first.
+    pub fn peek_iter(&mut self) -> impl Iterator<Item = &Iter::Item> {
+        self.buffered().iter().flatten()
+    }
+
+    ///Returns the offset and a reference to the first value that satisfies `pred`, without consuming anything.
+    ///Values are only read from the underlying iterator as far as the search goes, which stops at the end of the iterator or after `BUFSIZE` values.
+    pub fn peek_find(
+        &mut self,
+        pred: impl Fn(&Iter::Item) -> bool,
+    ) -> Option<(usize, &Iter::Item)> {
+        let offset = (0..BUFSIZE)
+            .map_while(|offset| {
+                self.demand(offset + 1);
+                self.peek[self.index(offset)].as_ref().map(&pred)
+            })
+            .position(|found| found)?;
+        self.peek[self.index(offset)]
+            .as_ref()
+            .map(|item| (offset, item))
+    }
+}
+
+impl<Iter, T, E, const BUFSIZE: usize> PutBackPeekMore<Iter, BUFSIZE>
+where
+    Iter: Iterator<Item = Result<T, E>>,
+{
+    ///Create a new iterator over a fallible source, returning the first error read while filling the buffer.
+    ///Nothing is read from `iter` after that error.
+    pub fn new_try(mut iter: Iter) -> Result<Self, E> {
+        let mut peek: [Option<Iter::Item>; BUFSIZE] = [(); BUFSIZE].map(|_| None);
+        for slot in &mut peek {
+            match iter.next() {
+                Some(Err(err)) => return Err(err),
+                next => *slot = next,
+            }
+        }
+        Ok(Self {
+            iter,
+            peek,
+            head: 0,
+            len: BUFSIZE,
+            retained: 0,
+            consumed: 0,
+            policy: RefillPolicy::Minimal,
+            guarded: false,
+        })
+    }
+
+    ///Consume the iterator, only yielding the `Ok` values and dropping the errors. Buffered values are included.
+    ///Wrap the result in a new `PutBackPeekMore` to keep peeking and putting back.
+    pub fn filter_ok(self) -> impl Iterator<Item = T> {
+        self.filter_map(Result::ok)
+    }
+}
+
+impl<Iter, const BUFSIZE: usize> PutBackPeekMore<Iter, BUFSIZE>
+where
+    Iter: Iterator<Item = u8>,
+{
+    ///Copy the next bytes of the iterator into `buf` without consuming them, and return how many were copied.
+    ///At most `BUFSIZE` bytes are copied, and fewer if the iterator ends first.
+    pub fn peek_bytes(&mut self, buf: &mut [u8]) -> usize {
+        let amount = buf.len().min(BUFSIZE);
+        self.demand(amount);
+        let copied = self.buffered_len().min(amount);
+        let bytes = self.window(copied).iter().flatten();
+        for (out, byte) in buf.iter_mut().zip(bytes) {
+            *out = *byte;
         }
+        copied
+    }
+}
+
+impl<F, T, const BUFSIZE: usize> PutBackPeekMore<core::iter::FromFn<F>, BUFSIZE>
+where
+    F: FnMut() -> Option<T>,
+{
+    ///Create a new iterator from a closure, like `core::iter::from_fn`.
+    ///```
+    ///use putbackpeekmore::PutBackPeekMore;
+    ///
+    ///let mut count = 0;
+    ///let mut iter: PutBackPeekMore<_, 2> = PutBackPeekMore::from_fn(|| {
+    ///    count += 1;
+    ///    (count <= 3).then_some(count)
+    ///});
+    ///assert_eq!(iter.peek(), &Some(1));
+    ///assert_eq!(iter.next(), Some(1));
+    ///assert_eq!(iter.next(), Some(2));
+    ///assert_eq!(iter.next(), Some(3));
+    ///assert_eq!(iter.next(), None);
+    ///```
+    pub fn from_fn(f: F) -> Self {
+        Self::new(core::iter::from_fn(f))
     }
 }
 
@@ -86,30 +1089,268 @@ where
         f.debug_struct("PutBackPeekMore")
             .field("iter", &"...")
             .field("peek", &self.peek)
-            .field("fizz", &self.fizz)
+            .field("head", &self.head)
+            .field("len", &self.len)
+            .field("retained", &self.retained)
+            .field("consumed", &self.consumed)
+            .field("policy", &self.policy)
+            .field("guarded", &self.guarded)
             .finish()
     }
 }
 
-impl<Iter, const PEEK: usize> Iterator for PutBackPeekMore<Iter, PEEK>
+impl<Iter, const PEEK: usize> Clone for PutBackPeekMore<Iter, PEEK>
 where
-    Iter: Iterator,
+    Iter: Iterator + Clone,
+    Iter::Item: Clone,
 {
-    type Item = Iter::Item;
-    ///Consume the iterator.
-    fn next(&mut self) -> Option<Self::Item> {
-        self.demand(1);
-        let out = self.peek[self.fizz].take();
-        self.fizz += 1;
-        out
+    fn clone(&self) -> Self {
+        Self {
+            iter: self.iter.clone(),
+            peek: self.peek.clone(),
+            head: self.head,
+            len: self.len,
+            retained: self.retained,
+            consumed: self.consumed,
+            policy: self.policy,
+            guarded: self.guarded,
+        }
     }
 }
 
-#[cfg(test)]
-mod tests {
-    extern crate std;
-    use crate::PutBackPeekMore;
-
+///Compares the remaining values of both iterators, working on clones so neither is consumed.
+impl<Iter, const PEEK: usize> PartialEq for PutBackPeekMore<Iter, PEEK>
+where
+    Iter: Iterator + Clone,
+    Iter::Item: Clone + PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.clone().eq(other.clone())
+    }
+}
+
+impl<Iter, const PEEK: usize> Eq for PutBackPeekMore<Iter, PEEK>
+where
+    Iter: Iterator + Clone,
+    Iter::Item: Clone + Eq,
+{
+}
+
+///Compares the remaining values of both iterators lexicographically, working on clones so neither is consumed.
+impl<Iter, const PEEK: usize> PartialOrd for PutBackPeekMore<Iter, PEEK>
+where
+    Iter: Iterator + Clone,
+    Iter::Item: Clone + PartialOrd,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        self.clone().partial_cmp(other.clone())
+    }
+}
+
+///Compares the remaining values of both iterators lexicographically, working on clones so neither is consumed.
+impl<Iter, const PEEK: usize> Ord for PutBackPeekMore<Iter, PEEK>
+where
+    Iter: Iterator + Clone,
+    Iter::Item: Clone + Ord,
+{
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.clone().cmp(other.clone())
+    }
+}
+
+impl<Iter, const PEEK: usize> Iterator for PutBackPeekMore<Iter, PEEK>
+where
+    Iter: Iterator,
+{
+    type Item = Iter::Item;
+    ///Consume the iterator.
+    ///The value is handed over, so the values retained behind it can't be gone back to anymore and are dropped like with `commit`.
+    fn next(&mut self) -> Option<Self::Item> {
+        self.commit();
+        self.demand(1);
+        let out = self.peek[self.head].take();
+        self.advance();
+        self.consumed = self.consumed.wrapping_add(usize::from(out.is_some()));
+        out
+    }
+
+    ///Counts the buffered values on top of the size hint of the structs `iter` field.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let buffered = self.buffered_len();
+        if buffered < self.len {
+            return (buffered, Some(buffered));
+        }
+        let (lower, upper) = self.iter.size_hint();
+        (
+            lower.saturating_add(buffered),
+            upper.and_then(|upper| upper.checked_add(buffered)),
+        )
+    }
+
+    ///Consume the buffered values, then hand the rest over to the structs `iter` field.
+    fn fold<B, F>(self, init: B, mut f: F) -> B
+    where
+        F: FnMut(B, Self::Item) -> B,
+    {
+        let (buffered, iter) = self.into_split();
+        let acc = buffered.fold(init, &mut f);
+        match iter {
+            Some(iter) => iter.fold(acc, f),
+            None => acc,
+        }
+    }
+
+    ///Run `f` on the buffered values, then hand the rest over to the structs `iter` field.
+    fn for_each<F>(self, mut f: F)
+    where
+        F: FnMut(Self::Item),
+    {
+        let (buffered, iter) = self.into_split();
+        buffered.for_each(&mut f);
+        if let Some(iter) = iter {
+            iter.for_each(f);
+        }
+    }
+
+    ///Counts the buffered values, then hands the rest over to the structs `iter` field.
+    fn count(self) -> usize {
+        let (buffered, iter) = self.into_split();
+        buffered.count() + iter.map_or(0, Iterator::count)
+    }
+
+    ///Searches the buffered values first, then hands the rest of the search over to the structs `iter` field.
+    fn position<P>(&mut self, mut pred: P) -> Option<usize>
+    where
+        P: FnMut(Self::Item) -> bool,
+    {
+        self.commit();
+        let mut index = 0;
+        while self.len > 0 {
+            let item = self.peek[self.head].take();
+            self.advance();
+            self.consumed = self.consumed.wrapping_add(usize::from(item.is_some()));
+            if pred(item?) {
+                return Some(index);
+            }
+            index += 1;
+        }
+        let mut read = 0;
+        let found = self.iter.position(|item| {
+            read += 1;
+            pred(item)
+        });
+        self.consumed = self.consumed.wrapping_add(read);
+        found.map(|found| found + index)
+    }
+
+    ///Returns the last value, reading the rest of the structs `iter` field if the buffer didn't reach the end.
+    fn last(self) -> Option<Self::Item> {
+        let (buffered, iter) = self.into_split();
+        let last = buffered.last();
+        iter.and_then(Iterator::last).or(last)
+    }
+
+    ///Returns the largest value, comparing the buffered values and put back values together with the rest of the structs `iter` field.
+    fn max(self) -> Option<Self::Item>
+    where
+        Self::Item: Ord,
+    {
+        let (buffered, iter) = self.into_split();
+        buffered.chain(iter.into_iter().flatten()).max()
+    }
+
+    ///Returns the smallest value, comparing the buffered values and put back values together with the rest of the structs `iter` field.
+    fn min(self) -> Option<Self::Item>
+    where
+        Self::Item: Ord,
+    {
+        let (buffered, iter) = self.into_split();
+        buffered.chain(iter.into_iter().flatten()).min()
+    }
+}
+
+///Values are taken from the back of the structs `iter` field first, then from the back of the buffer.
+///A `None` in the last buffered slot means the buffer found the end of the structs `iter` field, so only the buffered values are yielded then.
+///Other `None` slots, like ones that were put back, are skipped and stay in place.
+impl<Iter, const PEEK: usize> DoubleEndedIterator for PutBackPeekMore<Iter, PEEK>
+where
+    Iter: DoubleEndedIterator,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let last = (0..self.len)
+            .rev()
+            .find(|&offset| self.peek[self.index(offset)].is_some());
+        let found_end = last.map_or(self.len, |last| last + 1) < self.len;
+        if !found_end && let Some(item) = self.iter.next_back() {
+            return Some(item);
+        }
+        let last = last?;
+        let item = self.peek[self.index(last)].take();
+        if found_end {
+            for offset in last + 1..self.len {
+                self.peek[self.index(offset)] = None;
+            }
+            self.len = last + 1;
+        } else {
+            self.len = last;
+        }
+        item
+    }
+}
+
+impl<Iter, const PEEK: usize> ExactSizeIterator for PutBackPeekMore<Iter, PEEK> where
+    Iter: ExactSizeIterator
+{
+}
+
+///Merge two sorted iterators into one sorted iterator, by peeking at both and consuming the smaller value.
+///When both values are equal, the one from `a` comes first.
+pub fn merge<A, B, const N: usize, const M: usize>(
+    mut a: PutBackPeekMore<A, N>,
+    mut b: PutBackPeekMore<B, M>,
+) -> impl Iterator<Item = A::Item>
+where
+    A: Iterator,
+    B: Iterator<Item = A::Item>,
+    A::Item: Ord,
+{
+    core::iter::from_fn(move || match (a.peek_checked(), b.peek_checked()) {
+        (Some(x), Some(y)) if y < x => b.next(),
+        (Some(_), _) => a.next(),
+        (None, _) => b.next(),
+    })
+}
+
+///Adds `put_back_peek_more` to every iterator, so the buffer size is the only thing to spell out.
+///```
+///use putbackpeekmore::PutBackPeekMoreExt;
+///
+///let mut iter = (0..10).put_back_peek_more::<4>();
+///assert_eq!(iter.peek_checked(), Some(&0));
+///```
+pub trait PutBackPeekMoreExt: Iterator + Sized {
+    ///Wrap the iterator in a `PutBackPeekMore` with a buffer of `N` values.
+    fn put_back_peek_more<const N: usize>(self) -> PutBackPeekMore<Self, N> {
+        PutBackPeekMore::new(self)
+    }
+}
+
+impl<Iter: Iterator> PutBackPeekMoreExt for Iter {}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use crate::{
+        Checkpoint, PeekError, Peeked, PutBackPeekMore, PutBackPeekMoreExt, RefillPolicy,
+        SeekError, merge,
+    };
+    use core::cell::Cell;
+
+    ///Wraps `iter` so every value read from it is counted in `polls`.
+    fn counted<I: Iterator>(iter: I, polls: &Cell<usize>) -> impl Iterator<Item = I::Item> {
+        iter.inspect(|_| polls.set(polls.get() + 1))
+    }
+
     #[test]
     fn test_peek_value() {
         let mut iter: PutBackPeekMore<core::ops::Range<i32>, 3> = PutBackPeekMore::new(0..10);
@@ -151,4 +1392,1124 @@ mod tests {
         iter.next();
         assert_eq!(iter.peek_value(3), &[Some(1), Some(2), Some(3)])
     }
+
+    #[test]
+    fn put_back_full_buffer() {
+        let mut iter: PutBackPeekMore<_, 3> = PutBackPeekMore::new(0..10);
+        iter.put_back(Some(9));
+        assert_eq!(iter.peek_value(3), &[Some(9), Some(0), Some(1)]);
+        assert_eq!(iter.next(), Some(9));
+        assert_eq!(iter.next(), Some(0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn put_back_overrun() {
+        let mut iter: PutBackPeekMore<_, 3> = PutBackPeekMore::new(0..10);
+        iter.peek_value(3);
+        iter.put_back(Some(9));
+    }
+
+    #[test]
+    fn peek_after_refill() {
+        let mut iter: PutBackPeekMore<_, 3> = PutBackPeekMore::new(0..10);
+        iter.next();
+        iter.next();
+        assert_eq!(iter.peek_value(2), &[Some(2), Some(3)]);
+        iter.put_back(Some(1));
+        assert_eq!(iter.peek_value(3), &[Some(1), Some(2), Some(3)]);
+    }
+
+    #[test]
+    fn replace_source() {
+        let mut iter: PutBackPeekMore<_, 2> = PutBackPeekMore::new(0..3);
+        iter.peek_value(2);
+        let mut iter = iter.replace_source(100..102);
+        assert_eq!(iter.next(), Some(0));
+        assert_eq!(iter.next(), Some(1));
+        assert_eq!(iter.next(), Some(100));
+        assert_eq!(iter.next(), Some(101));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn peek_checked() {
+        let mut iter: PutBackPeekMore<_, 3> = PutBackPeekMore::new(0..2);
+        assert_eq!(iter.peek_checked(), Some(&0));
+        iter.next();
+        iter.next();
+        assert_eq!(iter.peek_checked(), None);
+    }
+
+    #[test]
+    fn long_stream_boundaries() {
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..1000);
+        let mut expected = 0;
+        while let Some(&val) = iter.peek_checked() {
+            assert_eq!(iter.next(), Some(val));
+            assert_eq!(val, expected);
+            expected += 1;
+        }
+        assert_eq!(expected, 1000);
+        assert_eq!(iter.next(), None);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn into_vec() {
+        let mut iter: PutBackPeekMore<_, 3> = PutBackPeekMore::new(0..5);
+        iter.next();
+        iter.put_back(Some(10));
+        assert_eq!(iter.into_vec(), std::vec![10, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn peek_map() {
+        let mut iter: PutBackPeekMore<_, 2> = PutBackPeekMore::new(["abc", "de"].into_iter());
+        assert_eq!(iter.peek_map(|s| s.len()), Some(3));
+        assert_eq!(iter.next(), Some("abc"));
+        assert_eq!(iter.peek_map(|s| s.len()), Some(2));
+        iter.next();
+        assert_eq!(iter.peek_map(|s| s.len()), None);
+    }
+
+    #[test]
+    fn step_by() {
+        let mut iter: PutBackPeekMore<_, 3> = PutBackPeekMore::new(0..10);
+        assert_eq!(iter.peek(), &Some(0));
+        let mut iter = iter.step_by(2);
+        assert_eq!(iter.peek_value(3), &[Some(0), Some(2), Some(4)]);
+        assert_eq!(iter.next(), Some(0));
+        assert_eq!(iter.peek_value(3), &[Some(2), Some(4), Some(6)]);
+        assert_eq!(iter.nth(2), Some(6));
+        assert_eq!(iter.next(), Some(8));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn step_by_zero() {
+        let iter: PutBackPeekMore<_, 3> = PutBackPeekMore::new(0..10);
+        let _ = iter.step_by(0);
+    }
+
+    #[test]
+    fn buffered_len() {
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..3);
+        assert_eq!(iter.buffered_len(), 0);
+        iter.peek_value(4);
+        assert_eq!(iter.buffered_len(), 3);
+        iter.next();
+        assert_eq!(iter.buffered_len(), 2);
+    }
+
+    #[test]
+    fn peek_partition_point() {
+        let mut iter: PutBackPeekMore<_, 5> = PutBackPeekMore::new([1, 2, 3, 10, 11].into_iter());
+        assert_eq!(iter.peek_partition_point(|x| *x < 5), 3);
+        assert_eq!(iter.next(), Some(1));
+    }
+
+    #[test]
+    fn take() {
+        let mut iter: PutBackPeekMore<_, 2> = PutBackPeekMore::new(0..10);
+        assert_eq!(iter.peek(), &Some(0));
+        let mut iter = iter.take(3);
+        assert_eq!(iter.peek_value(2), &[Some(0), Some(1)]);
+        assert_eq!(iter.next(), Some(0));
+        assert_eq!(iter.next(), Some(1));
+        assert_eq!(iter.next(), Some(2));
+        assert_eq!(iter.next(), None);
+
+        let iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..10);
+        let mut iter = iter.take(2);
+        assert_eq!(iter.peek_value(3), &[Some(0), Some(1), None]);
+    }
+
+    #[test]
+    fn peek_distinct_run() {
+        let mut iter: PutBackPeekMore<_, 5> = PutBackPeekMore::new([1, 1, 2, 2, 3].into_iter());
+        assert_eq!(iter.peek_distinct_run(|a, b| a == b), 3);
+        assert_eq!(iter.next(), Some(1));
+
+        let mut iter: PutBackPeekMore<core::iter::Empty<i32>, 5> =
+            PutBackPeekMore::new(core::iter::empty());
+        assert_eq!(iter.peek_distinct_run(|a, b| a == b), 0);
+    }
+
+    #[test]
+    fn rewind() {
+        let mut iter: PutBackPeekMore<_, 3> = PutBackPeekMore::new(0..10);
+        assert_eq!(iter.rewind(), Err(()));
+        assert_eq!(iter.next_retained(), Some(0));
+        assert_eq!(iter.rewind(), Ok(()));
+        assert_eq!(iter.peek(), &Some(0));
+        assert_eq!(iter.next(), Some(0));
+        assert_eq!(iter.rewind(), Err(()));
+        assert_eq!(iter.peek(), &Some(1));
+    }
+
+    #[test]
+    fn new_try() {
+        let source = [Ok(0), Err("bad read"), Ok(2)];
+        let iter: Result<PutBackPeekMore<_, 3>, _> = PutBackPeekMore::new_try(source.into_iter());
+        assert_eq!(iter.unwrap_err(), "bad read");
+
+        let source: [Result<i32, &str>; 2] = [Ok(0), Ok(1)];
+        let mut iter: PutBackPeekMore<_, 3> = PutBackPeekMore::new_try(source.into_iter()).unwrap();
+        assert_eq!(iter.next(), Some(Ok(0)));
+    }
+
+    #[test]
+    fn peek_or() {
+        let mut iter: PutBackPeekMore<_, 2> = PutBackPeekMore::new(0..1);
+        assert_eq!(iter.peek_or(&10), &0);
+        iter.next();
+        assert_eq!(iter.peek_or(&10), &10);
+    }
+
+    #[test]
+    fn peek_or_else() {
+        let mut called = false;
+        let mut iter: PutBackPeekMore<_, 2> = PutBackPeekMore::new(0..1);
+        assert_eq!(iter.peek_or_else(|| unreachable!()), &0);
+        iter.next();
+        assert_eq!(
+            iter.peek_or_else(|| {
+                called = true;
+                &10
+            }),
+            &10
+        );
+        assert!(called);
+    }
+
+    #[test]
+    fn fold() {
+        let mut iter: PutBackPeekMore<_, 8> = PutBackPeekMore::new(0..1000);
+        iter.next();
+        iter.put_back(Some(0));
+        let folded = iter.fold((0, 0), |(count, sum), x| (count + 1, sum + x));
+        assert_eq!(folded, (1000, (0..1000).sum::<i32>()));
+
+        let iter: PutBackPeekMore<_, 8> = PutBackPeekMore::new(0..3);
+        assert_eq!(
+            iter.fold((0, 0), |(count, sum), x| (count + 1, sum + x)),
+            (3, 3)
+        );
+    }
+
+    #[test]
+    fn peek_is() {
+        let mut iter: PutBackPeekMore<_, 2> = PutBackPeekMore::new(0..3);
+        assert!(iter.peek_is(|x| *x == 0));
+        iter.next();
+        assert!(!iter.peek_is(|x| *x == 0));
+        iter.nth(1);
+        assert!(!iter.peek_is(|_| unreachable!()));
+    }
+
+    #[test]
+    fn refill_policy() {
+        let polls = Cell::new(0);
+        let source = counted(0..10, &polls);
+        let mut iter: PutBackPeekMore<_, 4> =
+            PutBackPeekMore::with_policy(source, RefillPolicy::Eager);
+        assert_eq!(polls.get(), 0);
+        assert_eq!(iter.peek(), &Some(0));
+        assert_eq!(polls.get(), 4);
+
+        let polls = Cell::new(0);
+        let source = counted(0..10, &polls);
+        let mut iter: PutBackPeekMore<_, 4> =
+            PutBackPeekMore::with_policy(source, RefillPolicy::Minimal);
+        assert_eq!(polls.get(), 0);
+        assert_eq!(iter.peek(), &Some(0));
+        assert_eq!(polls.get(), 1);
+        assert_eq!(iter.peek_value(3), &[Some(0), Some(1), Some(2)]);
+        assert_eq!(polls.get(), 3);
+    }
+
+    #[test]
+    fn minimal_policy_drain() {
+        let mut iter: PutBackPeekMore<_, 3> =
+            PutBackPeekMore::with_policy(0..10, RefillPolicy::Minimal);
+        iter.put_back(Some(10));
+        assert_eq!(iter.peek_value(3), &[Some(10), Some(0), Some(1)]);
+        assert!(iter.eq([10, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9]));
+    }
+
+    #[test]
+    fn peek_enumerated() {
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..3);
+        {
+            let mut pairs = iter.peek_enumerated();
+            assert_eq!(pairs.next(), Some((0, &0)));
+            assert_eq!(pairs.next(), Some((1, &1)));
+            assert_eq!(pairs.next(), Some((2, &2)));
+            assert_eq!(pairs.next(), None);
+        }
+        assert_eq!(iter.next(), Some(0));
+    }
+
+    #[test]
+    fn consume_exact() {
+        let mut iter: PutBackPeekMore<_, 5> = PutBackPeekMore::new(0..3);
+        assert_eq!(iter.consume_exact(5), Err(3));
+        assert_eq!(iter.peek_value(3), &[Some(0), Some(1), Some(2)]);
+        assert_eq!(iter.consume_exact(2), Ok(()));
+        assert_eq!(iter.next(), Some(2));
+    }
+
+    #[test]
+    fn try_consume_exact() {
+        let mut iter: PutBackPeekMore<_, 3> = PutBackPeekMore::new(0..20);
+        assert_eq!(iter.try_consume_exact(4), None);
+        assert_eq!(iter.try_consume_exact(3), Some(Ok(())));
+        assert!(iter.eq(3..20));
+    }
+
+    #[test]
+    #[should_panic]
+    fn consume_exact_overrun() {
+        let mut iter: PutBackPeekMore<_, 3> = PutBackPeekMore::new(0..20);
+        let _ = iter.consume_exact(4);
+    }
+
+    #[test]
+    fn peek_bytes() {
+        let mut iter: PutBackPeekMore<_, 8> = PutBackPeekMore::new(b"GET /index".iter().copied());
+        let mut buf = [0; 4];
+        assert_eq!(iter.peek_bytes(&mut buf), 4);
+        assert_eq!(&buf, b"GET ");
+        assert_eq!(iter.next(), Some(b'G'));
+
+        let mut iter: PutBackPeekMore<_, 8> = PutBackPeekMore::new(b"ab".iter().copied());
+        assert_eq!(iter.peek_bytes(&mut buf), 2);
+        assert_eq!(&buf[..2], b"ab");
+    }
+
+    #[test]
+    fn last() {
+        let mut iter: PutBackPeekMore<_, 8> = PutBackPeekMore::new(0..3);
+        iter.next();
+        assert_eq!(iter.last(), Some(2));
+
+        let mut iter: PutBackPeekMore<_, 3> = PutBackPeekMore::new(0..100);
+        iter.next();
+        assert_eq!(iter.last(), Some(99));
+
+        let mut iter: PutBackPeekMore<_, 3> = PutBackPeekMore::new(0..1);
+        iter.next();
+        assert_eq!(iter.last(), None);
+    }
+
+    #[test]
+    fn size_hint() {
+        let mut iter: PutBackPeekMore<_, 3> = PutBackPeekMore::new(0..10);
+        assert_eq!(iter.size_hint(), (10, Some(10)));
+        iter.next();
+        assert_eq!(iter.len(), 9);
+        iter.put_back(Some(0));
+        assert_eq!(iter.len(), 10);
+
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..2);
+        iter.next();
+        assert_eq!(iter.len(), 1);
+        assert!(!iter.is_exhausted());
+        iter.next();
+        assert!(iter.is_exhausted());
+        assert_eq!(iter.len(), 0);
+    }
+
+    #[test]
+    fn infinite_source() {
+        let mut iter: PutBackPeekMore<_, 2> = PutBackPeekMore::new((0..3).cycle());
+        for expected in [0, 1, 2, 0, 1, 2, 0] {
+            assert_eq!(iter.peek(), &Some(expected));
+            assert!(!iter.is_exhausted());
+            assert_eq!(iter.next(), Some(expected));
+        }
+        assert_eq!(iter.size_hint(), (usize::MAX, None));
+    }
+
+    #[test]
+    fn peek_nth() {
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..10);
+        iter.next();
+        assert_eq!(iter.peek_nth(0), &Some(1));
+        assert_eq!(iter.peek_nth(3), &Some(4));
+        assert_eq!(iter.next(), Some(1));
+    }
+
+    #[test]
+    fn try_peek_nth() {
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..2);
+        assert_eq!(iter.try_peek_nth(1), Some(&Some(1)));
+        assert_eq!(iter.try_peek_nth(3), Some(&None));
+        assert_eq!(iter.try_peek_nth(4), None);
+    }
+
+    #[test]
+    fn peek_cmp() {
+        let mut iter: PutBackPeekMore<_, 2> = PutBackPeekMore::new(5..10);
+        assert_eq!(iter.peek_cmp(&7), Some(core::cmp::Ordering::Less));
+        assert_eq!(iter.peek_cmp(&5), Some(core::cmp::Ordering::Equal));
+        assert_eq!(iter.nth(4), Some(9));
+        assert_eq!(iter.peek_cmp(&7), None);
+    }
+
+    #[test]
+    fn consume_until_pred() {
+        let mut iter: PutBackPeekMore<_, 2> = PutBackPeekMore::new("12345+6".chars());
+        assert_eq!(iter.consume_until_pred(|c| !c.is_ascii_digit()), 5);
+        assert_eq!(iter.next(), Some('+'));
+        assert_eq!(iter.consume_until_pred(|c| !c.is_ascii_digit()), 1);
+        assert_eq!(iter.next(), None);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn peek_prefix() {
+        let mut iter: PutBackPeekMore<_, 5> = PutBackPeekMore::new(0..2);
+        assert_eq!(iter.peek_prefix(5), std::vec![&0, &1]);
+        assert_eq!(iter.peek_prefix(1), std::vec![&0]);
+    }
+
+    #[test]
+    fn stream_position() {
+        let mut iter: PutBackPeekMore<_, 3> = PutBackPeekMore::new(0..10);
+        for expected in 0..3 {
+            assert_eq!(iter.stream_position(), expected);
+            iter.next();
+        }
+        assert_eq!(iter.stream_position(), 3);
+        iter.put_back(Some(2));
+        assert_eq!(iter.stream_position(), 2);
+        iter.peek_value(2);
+        assert_eq!(iter.stream_position(), 2);
+        iter.next();
+        iter.next();
+        assert_eq!(iter.stream_position(), 4);
+    }
+
+    #[test]
+    fn merge_sorted() {
+        let a: PutBackPeekMore<_, 2> = PutBackPeekMore::new([1, 3, 5].into_iter());
+        let b: PutBackPeekMore<_, 3> = PutBackPeekMore::new([2, 4, 6].into_iter());
+        assert!(merge(a, b).eq([1, 2, 3, 4, 5, 6]));
+
+        let a: PutBackPeekMore<_, 2> = PutBackPeekMore::new([1, 2].into_iter());
+        let b: PutBackPeekMore<_, 2> = PutBackPeekMore::new(0..1);
+        assert!(merge(a, b).eq([0, 1, 2]));
+    }
+
+    #[test]
+    fn peek_pair_checked() {
+        let mut iter: PutBackPeekMore<_, 2> = PutBackPeekMore::new("=".chars());
+        assert_eq!(iter.peek_pair_checked(), None);
+
+        let mut iter: PutBackPeekMore<_, 2> = PutBackPeekMore::new("==".chars());
+        assert_eq!(iter.peek_pair_checked(), Some((&'=', &'=')));
+        assert_eq!(iter.next(), Some('='));
+    }
+
+    #[test]
+    fn advance_window() {
+        let mut iter: PutBackPeekMore<_, 3> = PutBackPeekMore::new(0..5);
+        assert_eq!(iter.advance_window::<2>(), Some([0, 1]));
+        assert_eq!(iter.advance_window::<2>(), Some([1, 2]));
+        assert_eq!(iter.advance_window::<2>(), Some([2, 3]));
+        assert_eq!(iter.advance_window::<2>(), Some([3, 4]));
+        assert_eq!(iter.advance_window::<2>(), None);
+        assert_eq!(iter.next(), Some(4));
+    }
+
+    #[test]
+    #[should_panic]
+    fn advance_window_empty() {
+        let mut iter: PutBackPeekMore<_, 3> = PutBackPeekMore::new(0..5);
+        iter.advance_window::<0>();
+    }
+
+    #[test]
+    fn new_fast() {
+        let mut slow: PutBackPeekMore<_, 16> = PutBackPeekMore::new(0..3);
+        let mut fast: PutBackPeekMore<_, 16> = PutBackPeekMore::new_fast(0..3);
+        slow.peek_value(3);
+        fast.peek_value(3);
+        assert_eq!(slow.peek, fast.peek);
+        assert_eq!((slow.head, slow.len), (fast.head, fast.len));
+        assert!(slow.eq(fast));
+    }
+
+    #[test]
+    fn peek_nth_mut() {
+        let mut iter: PutBackPeekMore<_, 3> = PutBackPeekMore::new(0..5);
+        *iter.peek_nth_mut(2).unwrap() = 20;
+        assert_eq!(iter.next(), Some(0));
+        assert_eq!(iter.next(), Some(1));
+        assert_eq!(iter.next(), Some(20));
+        assert_eq!(iter.next(), Some(3));
+        iter.next();
+        assert_eq!(iter.peek_nth_mut(0), None);
+    }
+
+    #[test]
+    fn for_each() {
+        let mut iter: PutBackPeekMore<_, 8> = PutBackPeekMore::new(0..100);
+        iter.next();
+        iter.put_back(Some(0));
+        let mut count = 0;
+        let mut total = 0;
+        iter.for_each(|x| {
+            count += 1;
+            total += x;
+        });
+        assert_eq!((count, total), (100, (0..100).sum::<i32>()));
+    }
+
+    #[test]
+    fn peek_collect() {
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..10);
+        assert_eq!(iter.peek_collect::<4>(), ([0, 1, 2, 3], 4));
+        assert_eq!(iter.next(), Some(0));
+
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(1..3);
+        assert_eq!(iter.peek_collect::<4>(), ([1, 2, 0, 0], 2));
+    }
+
+    #[test]
+    fn clear_and_refill() {
+        let mut iter: PutBackPeekMore<_, 3> = PutBackPeekMore::new(0..10);
+        iter.peek_value(3);
+        iter.next();
+        iter.put_back(Some(10));
+        iter.clear_and_refill();
+        assert_eq!(iter.peek_value(3), &[Some(3), Some(4), Some(5)]);
+        assert!(iter.eq(3..10));
+    }
+
+    #[test]
+    fn filter_ok() {
+        let source = [Ok(1), Err("x"), Ok(3)];
+        let iter: PutBackPeekMore<_, 2> = PutBackPeekMore::new(source.into_iter());
+        assert!(iter.filter_ok().eq([1, 3]));
+
+        let iter: PutBackPeekMore<_, 2> = PutBackPeekMore::new(source.into_iter());
+        let mut iter: PutBackPeekMore<_, 2> = PutBackPeekMore::new(iter.filter_ok());
+        assert_eq!(iter.peek_value(2), &[Some(1), Some(3)]);
+    }
+
+    #[test]
+    fn peek_sum() {
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(1..=10);
+        assert_eq!(iter.peek_sum::<i32>(), 10);
+        assert_eq!(iter.peek_product::<i32>(), 24);
+        assert_eq!(iter.next(), Some(1));
+    }
+
+    #[test]
+    fn modify_last() {
+        let mut iter: PutBackPeekMore<_, 3> = PutBackPeekMore::new(0..10);
+        assert_eq!(iter.modify_last(|_| unreachable!()), Err(()));
+        assert_eq!(iter.next_retained(), Some(0));
+        assert_eq!(iter.modify_last(|x| *x += 10), Ok(()));
+        assert_eq!(iter.next(), Some(10));
+        assert_eq!(iter.modify_last(|_| unreachable!()), Err(()));
+        assert_eq!(iter.next(), Some(1));
+    }
+
+    #[test]
+    fn capacity() {
+        let iter: PutBackPeekMore<_, 3> = PutBackPeekMore::new(0..10);
+        assert_eq!(iter.capacity(), 3);
+        let iter: PutBackPeekMore<_, 16> = PutBackPeekMore::new(core::iter::empty::<()>());
+        assert_eq!(iter.capacity(), 16);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn peek_map_while() {
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new("12a3".chars());
+        assert_eq!(iter.peek_map_while(|c| c.to_digit(10)), std::vec![1, 2]);
+        assert_eq!(iter.next(), Some('1'));
+    }
+
+    #[test]
+    fn peek_rfind() {
+        let mut iter: PutBackPeekMore<_, 5> = PutBackPeekMore::new(1..10);
+        assert_eq!(iter.peek_rfind(|x| x % 2 == 0), Some(3));
+        assert_eq!(iter.peek_rfind(|x| *x > 5), None);
+    }
+
+    #[test]
+    fn prime() {
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..5);
+        assert_eq!(iter.peek(), &Some(0));
+        assert_eq!(iter.prime([10, 11]), 2);
+        assert!(iter.eq([0, 10, 11, 1, 2, 3, 4]));
+
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..5);
+        iter.peek_value(3);
+        assert_eq!(iter.prime([10, 11, 12]), 1);
+        assert!(iter.eq([0, 1, 2, 10, 3, 4]));
+
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..2);
+        iter.peek_value(3);
+        assert_eq!(iter.prime([10, 11, 12]), 2);
+        assert!(iter.eq([0, 1, 10, 11]));
+
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..10);
+        iter.peek_value(2);
+        iter.put_back(None);
+        assert_eq!(iter.prime([7]), 1);
+        assert_eq!(iter.peek, [None, Some(0), Some(1), Some(7)]);
+        assert_eq!(iter.next(), None);
+        assert!(iter.eq([0, 1, 7, 2, 3, 4, 5, 6, 7, 8, 9]));
+    }
+
+    #[test]
+    fn peek_range() {
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..10);
+        assert_eq!(iter.peek_range(1, 2), &[Some(1), Some(2)]);
+        iter.next();
+        assert_eq!(iter.peek_range(1, 3), &[Some(2), Some(3), Some(4)]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn peek_range_overrun() {
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..10);
+        iter.peek_range(2, 3);
+    }
+
+    #[test]
+    fn discard_buffered() {
+        let mut iter: PutBackPeekMore<_, 3> = PutBackPeekMore::new(0..10);
+        assert_eq!(iter.peek_value(3), &[Some(0), Some(1), Some(2)]);
+        assert_eq!(iter.discard_buffered(), 3);
+        assert_eq!(iter.peek(), &Some(3));
+        assert_eq!(iter.next(), Some(3));
+
+        let mut iter: PutBackPeekMore<_, 3> = PutBackPeekMore::new(0..2);
+        iter.peek_value(3);
+        assert_eq!(iter.discard_buffered(), 2);
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn count() {
+        let mut iter: PutBackPeekMore<_, 3> = PutBackPeekMore::new(0..7);
+        iter.next();
+        assert_eq!(iter.count(), 6);
+
+        let mut iter: PutBackPeekMore<_, 8> = PutBackPeekMore::new(0..7);
+        iter.next();
+        iter.put_back(Some(0));
+        assert_eq!(iter.count(), 7);
+    }
+
+    #[test]
+    fn new_guarded() {
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new_guarded(0..6);
+        iter.seek_relative(3).unwrap();
+        assert_eq!(iter.peek_value(2), &[Some(3), Some(4)]);
+        assert_eq!(iter.rewind(), Err(()));
+        iter.next();
+        assert_eq!(iter.peek_value(4), &[Some(4), Some(5), None, None]);
+        assert_eq!(iter.peek, [Some(4), Some(5), None, None]);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn consume_while() {
+        let mut iter: PutBackPeekMore<_, 2> = PutBackPeekMore::new("123x".chars());
+        assert_eq!(
+            iter.consume_while(char::is_ascii_digit),
+            std::vec!['1', '2', '3']
+        );
+        assert_eq!(iter.next(), Some('x'));
+        assert_eq!(iter.consume_while(char::is_ascii_digit), std::vec![]);
+    }
+
+    #[test]
+    fn compare_remaining() {
+        let mut a: PutBackPeekMore<_, 2> = PutBackPeekMore::new(0..5);
+        let b: PutBackPeekMore<_, 2> = PutBackPeekMore::new(1..5);
+        assert!(a < b);
+        a.next();
+        assert!(a == b);
+        assert_eq!(a.peek(), &Some(1));
+
+        let mut all = [
+            PutBackPeekMore::<_, 2>::new(3..4),
+            PutBackPeekMore::new(1..3),
+            PutBackPeekMore::new(1..2),
+        ];
+        all.sort();
+        assert_eq!(all[0].peek_value(2), &[Some(1), None]);
+        assert_eq!(all.map(|iter| iter.count()), [1, 2, 1]);
+    }
+
+    #[test]
+    fn seek_relative() {
+        let mut iter: PutBackPeekMore<_, 5> = PutBackPeekMore::new(0..10);
+        assert_eq!(iter.seek_relative(3), Ok(()));
+        assert_eq!(iter.peek(), &Some(3));
+        assert_eq!(iter.seek_relative(-2), Ok(()));
+        assert_eq!(iter.peek(), &Some(1));
+        assert_eq!(iter.seek_relative(-5), Err(SeekError::NotBuffered(1)));
+        assert_eq!(iter.peek(), &Some(1));
+        assert_eq!(iter.seek_relative(20), Err(SeekError::EndOfIterator(9)));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn peek_state() {
+        let mut iter: PutBackPeekMore<_, 2> = PutBackPeekMore::new(0..3);
+        assert!(matches!(iter.peek_state(), Peeked::Item(0)));
+        iter.nth(2);
+        assert_eq!(iter.peek_state(), Peeked::Eof);
+    }
+
+    #[test]
+    fn compact() {
+        let polls = Cell::new(0);
+        let source = counted(0..10, &polls);
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(source);
+        iter.peek_value(4);
+        iter.next();
+        iter.next();
+        assert_eq!((iter.head, iter.put_back_capacity()), (2, 2));
+        iter.compact();
+        assert_eq!((iter.head, iter.put_back_capacity()), (0, 2));
+        assert_eq!(polls.get(), 4);
+        assert_eq!(iter.peek, [Some(2), Some(3), None, None]);
+        iter.put_back(Some(1));
+        iter.put_back(Some(0));
+        assert_eq!(iter.put_back_capacity(), 0);
+        assert!(iter.eq(0..10));
+    }
+
+    #[test]
+    fn find_peek() {
+        let mut iter: PutBackPeekMore<_, 2> = PutBackPeekMore::new(0..10);
+        assert_eq!(iter.find_peek(|x| *x == 3), Some(&3));
+        assert_eq!(iter.next(), Some(3));
+        assert_eq!(iter.find_peek(|x| *x == 3), None);
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn split_peek() {
+        let mut iter: PutBackPeekMore<_, 3> = PutBackPeekMore::new(0..5);
+        assert_eq!(iter.split_peek(3), Some((&0, &[Some(1), Some(2)][..])));
+        iter.nth(4);
+        assert_eq!(iter.split_peek(3), None);
+    }
+
+    #[test]
+    fn scoped() {
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..10);
+        let (first, second) = iter.scoped(|iter| {
+            assert_eq!(iter.peek_value(4), &[Some(0), Some(1), Some(2), Some(3)]);
+            let mut taken = iter.by_ref().take(2);
+            (taken.next(), taken.next())
+        });
+        assert_eq!((first, second), (Some(0), Some(1)));
+        assert_eq!(iter.peek_value(2), &[Some(2), Some(3)]);
+        assert!(iter.eq(2..10));
+    }
+
+    #[test]
+    fn peek_run_end() {
+        let mut iter: PutBackPeekMore<_, 3> = PutBackPeekMore::new(["aa", "ab", "bc"].into_iter());
+        assert_eq!(iter.peek_run_end(|s| s.chars().next()), 2);
+        iter.nth(1);
+        assert_eq!(iter.peek_run_end(|s| s.chars().next()), 1);
+        iter.next();
+        assert_eq!(iter.peek_run_end(|s| s.chars().next()), 0);
+    }
+
+    #[test]
+    fn peek_in() {
+        let mut iter: PutBackPeekMore<_, 2> = PutBackPeekMore::new("a,;".chars());
+        assert!(!iter.peek_in(&[',', ';']));
+        assert_eq!(iter.consume_in(&[',', ';']), None);
+        assert_eq!(iter.next(), Some('a'));
+        assert!(iter.peek_in(&[',', ';']));
+        assert_eq!(iter.consume_in(&[',', ';']), Some(','));
+        assert_eq!(iter.consume_in(&[',', ';']), Some(';'));
+        assert!(!iter.peek_in(&[',', ';']));
+    }
+
+    #[test]
+    fn position() {
+        let mut iter: PutBackPeekMore<_, 3> = PutBackPeekMore::new(0..10);
+        assert_eq!(iter.position(|x| x == 5), Some(5));
+        assert_eq!(iter.next(), Some(6));
+        assert_eq!(iter.position(|x| x == 7), Some(0));
+        assert_eq!(iter.peek_value(2), &[Some(8), Some(9)]);
+        assert_eq!(iter.position(|x| x == 5), None);
+
+        let mut iter: PutBackPeekMore<_, 8> = PutBackPeekMore::new(0..3);
+        assert_eq!(iter.position(|x| x == 5), None);
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn drain_with() {
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..50);
+        let mut count = 0;
+        iter.drain_with(|_| count += 1);
+        assert_eq!(count, 50);
+        assert!(iter.is_exhausted());
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn peek_nth_or() {
+        let mut iter: PutBackPeekMore<_, 8> = PutBackPeekMore::new(0..3);
+        assert_eq!(iter.peek_nth_or(1, &9), &1);
+        assert_eq!(iter.peek_nth_or(5, &9), &9);
+        assert_eq!(iter.next(), Some(0));
+    }
+
+    #[test]
+    fn max_min() {
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..5);
+        iter.next();
+        iter.put_back(Some(100));
+        assert_eq!(Iterator::max(iter), Some(100));
+
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..5);
+        iter.next();
+        iter.put_back(Some(-1));
+        assert_eq!(Iterator::min(iter.clone()), Some(-1));
+        iter.next();
+        assert_eq!(Iterator::min(iter), Some(1));
+    }
+
+    #[test]
+    fn peek_while_bounded() {
+        let mut iter: PutBackPeekMore<_, 8> = PutBackPeekMore::new([1, 1, 1, 1, 2].into_iter());
+        assert_eq!(iter.peek_while_bounded(2, |&x| x == 1), &[Some(1), Some(1)]);
+        assert_eq!(iter.peek_while_bounded(8, |&x| x == 1).len(), 4);
+        assert_eq!(iter.peek_while_bounded(8, |&x| x == 2), &[]);
+        assert_eq!(iter.next(), Some(1));
+    }
+
+    #[test]
+    fn normalize() {
+        let shuffle = |iter: &mut PutBackPeekMore<_, 4>| {
+            iter.peek_nth(2);
+            iter.next();
+            iter.put_back(Some(10));
+            iter.put_back(Some(11));
+            iter.next();
+            iter.put_back(Some(12));
+            iter.next_retained();
+        };
+        let mut plain: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..10);
+        let mut normalized: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..10);
+        shuffle(&mut plain);
+        shuffle(&mut normalized);
+        normalized.normalize();
+        assert_eq!(normalized.head, 0);
+        assert!(plain.eq(normalized));
+    }
+
+    #[test]
+    fn next_mut_or() {
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..1);
+        *iter.next_mut_or(5) += 1;
+        assert_eq!(iter.next(), Some(1));
+        *iter.next_mut_or(5) += 1;
+        assert_eq!(iter.next(), Some(6));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn ring_buffer() {
+        let polls = Cell::new(0);
+        let source = counted(0..10, &polls);
+        let mut iter: PutBackPeekMore<_, 4> =
+            PutBackPeekMore::with_policy(source, RefillPolicy::Minimal);
+        assert_eq!(iter.peek_value(2), &[Some(0), Some(1)]);
+        iter.next();
+        iter.next();
+        iter.next();
+        assert_eq!(iter.peek_value(4), &[Some(3), Some(4), Some(5), Some(6)]);
+        assert_eq!(polls.get(), 7);
+        iter.next();
+        iter.next();
+        iter.put_back(Some(40));
+        iter.put_back(Some(30));
+        assert_eq!(iter.peek_value(4), &[Some(30), Some(40), Some(5), Some(6)]);
+        assert_eq!(polls.get(), 7);
+        assert!(iter.eq([30, 40, 5, 6, 7, 8, 9]));
+    }
+
+    #[test]
+    fn try_peek() {
+        let mut iter: PutBackPeekMore<_, 3> = PutBackPeekMore::new(0..2);
+        assert_eq!(iter.try_peek(3), Ok(&[Some(0), Some(1), None][..]));
+        assert_eq!(iter.try_peek(4), Err(PeekError::AmountTooLarge));
+    }
+
+    #[test]
+    fn try_put_back() {
+        let mut iter: PutBackPeekMore<_, 3> = PutBackPeekMore::new(0..10);
+        iter.peek_value(3);
+        assert_eq!(iter.try_put_back(Some(9)), Err(PeekError::PutBackFull));
+        iter.next();
+        assert_eq!(iter.try_put_back(Some(9)), Ok(()));
+        assert!(iter.eq([9, 1, 2, 3, 4, 5, 6, 7, 8, 9]));
+    }
+
+    #[test]
+    fn put_back_n() {
+        let mut iter: PutBackPeekMore<_, 6> = PutBackPeekMore::new("abcdef".chars());
+        iter.peek_value(6);
+        let token: std::string::String = iter.by_ref().take(3).collect();
+        assert_eq!(iter.remaining_putback_capacity(), 3);
+        assert_eq!(iter.put_back_n(token.chars()), Ok(()));
+        assert_eq!(iter.remaining_putback_capacity(), 0);
+        assert_eq!(iter.put_back_n(['x']), Err(PeekError::PutBackFull));
+        assert!(iter.eq("abcdef".chars()));
+
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..10);
+        iter.next();
+        iter.peek_value(4);
+        assert_eq!(iter.put_back_n([7, 8]), Err(PeekError::PutBackFull));
+        assert!(iter.eq(1..10));
+    }
+
+    #[test]
+    fn put_back_n_after_refill() {
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..10);
+        for _ in 0..3 {
+            iter.next();
+        }
+        let token = [iter.next().unwrap(), iter.next().unwrap()];
+        assert_eq!(iter.remaining_putback_capacity(), 4);
+        assert_eq!(iter.put_back_n(token), Ok(()));
+        assert!(iter.eq(3..10));
+    }
+
+    #[test]
+    fn put_back_peek_more() {
+        let mut iter = (0..10).put_back_peek_more::<4>();
+        assert_eq!(iter.capacity(), 4);
+        assert_eq!(iter.peek_value(2), &[Some(0), Some(1)]);
+        assert!(iter.eq(0..10));
+    }
+
+    #[test]
+    fn checkpoint() {
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..10);
+        iter.next();
+        let start = iter.checkpoint();
+        assert_eq!(iter.advance_cursor(), Some(&1));
+        assert_eq!(iter.advance_cursor(), Some(&2));
+        assert_eq!(iter.reset_to(start), Ok(()));
+        assert_eq!(iter.peek(), &Some(1));
+
+        iter.advance_cursor();
+        let second = iter.checkpoint();
+        iter.reset_to(start).unwrap();
+        assert_eq!(iter.reset_to(second), Ok(()));
+        assert_eq!(iter.peek(), &Some(2));
+
+        iter.commit();
+        assert_eq!(iter.reset_to(start), Err(SeekError::NotBuffered(0)));
+        assert_eq!(iter.next(), Some(2));
+        assert_eq!(
+            iter.reset_to(Checkpoint { consumed: 11 }),
+            Err(SeekError::EndOfIterator(7))
+        );
+    }
+
+    #[test]
+    fn next_drops_retained() {
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..10);
+        assert_eq!(iter.next_retained(), Some(0));
+        assert_eq!(iter.next(), Some(1));
+        iter.commit();
+        assert_eq!(iter.seek_relative(-1), Err(SeekError::NotBuffered(0)));
+        assert!(iter.peek.iter().all(Option::is_none));
+        assert!(iter.eq(2..10));
+    }
+
+    #[test]
+    fn checkpoint_counts_only_values() {
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..10);
+        let start = iter.checkpoint();
+        assert_eq!(iter.position(|x| x == 10), None);
+        assert!(iter.reset_to(start).is_err());
+
+        let end = iter.checkpoint();
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.checkpoint(), end);
+
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..10);
+        iter.peek_value(4);
+        assert_eq!(iter.discard_buffered(), 4);
+        assert_eq!(iter.checkpoint(), Checkpoint { consumed: 4 });
+    }
+
+    #[test]
+    fn next_if() {
+        let mut iter: PutBackPeekMore<_, 2> = PutBackPeekMore::new("ab1".chars());
+        assert_eq!(iter.next_if(char::is_ascii_digit), None);
+        assert_eq!(iter.next_if(char::is_ascii_alphabetic), Some('a'));
+        assert_eq!(iter.next_if_eq(&'a'), None);
+        assert_eq!(iter.next_if_eq(&'b'), Some('b'));
+        assert_eq!(iter.next(), Some('1'));
+        assert_eq!(iter.next_if(|_| true), None);
+    }
+
+    #[test]
+    fn peek_mut() {
+        let mut iter: PutBackPeekMore<_, 3> = PutBackPeekMore::new(0..2);
+        *iter.peek_mut() = Some(10);
+        for slot in iter.peek_value_mut(3) {
+            *slot = slot.map_or(Some(20), |value| Some(value + 1));
+        }
+        assert!(iter.eq([11, 2, 20]));
+
+        let mut iter: PutBackPeekMore<_, 3> = PutBackPeekMore::new(0..5);
+        iter.next();
+        iter.next();
+        iter.peek_value_mut(3)[1] = None;
+        assert_eq!(iter.next(), Some(2));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next(), Some(4));
+    }
+
+    #[test]
+    fn next_back() {
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..6);
+        iter.next();
+        iter.put_back(Some(10));
+        assert_eq!(iter.next_back(), Some(5));
+        assert!(iter.rev().eq([4, 3, 2, 1, 10]));
+
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..2);
+        assert_eq!(iter.next_back(), Some(1));
+        assert_eq!(iter.next_back(), Some(0));
+        assert_eq!(iter.next_back(), None);
+        assert_eq!(iter.next(), None);
+
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..10);
+        iter.peek_value(4);
+        assert_eq!(iter.next_back(), Some(9));
+        assert_eq!(iter.len(), 9);
+        assert!(iter.eq(0..9));
+
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..3);
+        iter.peek_value(2);
+        iter.put_back(None);
+        assert_eq!(iter.next_back(), Some(2));
+        assert_eq!(iter.next_back(), Some(1));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next(), Some(0));
+        assert_eq!(iter.next(), None);
+
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..2);
+        iter.peek_value(4);
+        assert_eq!(iter.next_back(), Some(1));
+        assert_eq!(iter.peek_value(2), &[Some(0), None]);
+        assert_eq!(iter.next_back(), Some(0));
+        assert_eq!(iter.next_back(), None);
+    }
+
+    #[test]
+    fn lazy_new() {
+        let polls = Cell::new(0);
+        let source = counted(0..10, &polls);
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(source);
+        assert_eq!(polls.get(), 0);
+        assert_eq!(iter.next(), Some(0));
+        assert_eq!(polls.get(), 1);
+    }
+
+    #[test]
+    fn into_parts() {
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..10);
+        iter.next();
+        iter.put_back(Some(20));
+        let (buffered, rest) = iter.into_parts();
+        assert!(buffered.chain(rest).eq([20, 1, 2, 3, 4, 5, 6, 7, 8, 9]));
+
+        let iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..10);
+        assert_eq!(iter.into_inner(), Ok(0..10));
+
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..10);
+        iter.peek();
+        let iter = iter.into_inner().unwrap_err();
+        assert!(iter.eq(0..10));
+
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..10);
+        iter.peek_value(4);
+        iter.next();
+        iter.next();
+        iter.next();
+        let iter = iter.into_inner().unwrap_err();
+        assert!(iter.eq(3..10));
+    }
+
+    #[test]
+    fn peek_iter() {
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..3);
+        assert!(iter.peek_iter().eq(&[0, 1, 2]));
+        iter.next();
+        iter.put_back(Some(7));
+        assert!(iter.peek_iter().eq(&[7, 1, 2]));
+    }
+
+    #[test]
+    fn peek_find() {
+        let polls = Cell::new(0);
+        let source = counted("ab;cd;".chars(), &polls);
+        let mut iter: PutBackPeekMore<_, 4> =
+            PutBackPeekMore::with_policy(source, RefillPolicy::Minimal);
+        assert_eq!(iter.peek_find(|c| *c == ';'), Some((2, &';')));
+        assert_eq!(polls.get(), 3);
+        assert_eq!(iter.peek_find(|c| *c == 'x'), None);
+        assert_eq!(iter.next(), Some('a'));
+    }
+
+    #[test]
+    fn peek_find_stops_at_end() {
+        let calls = Cell::new(0);
+        let source = core::iter::from_fn(|| {
+            let call = calls.get();
+            calls.set(call + 1);
+            (call != 2).then_some(call)
+        });
+        let mut iter: PutBackPeekMore<_, 8> = PutBackPeekMore::new(source);
+        assert_eq!(iter.peek_find(|&x| x == 3), None);
+        assert_eq!(calls.get(), 3);
+    }
+
+    #[test]
+    fn put_back_after_peek() {
+        let mut iter: PutBackPeekMore<_, 3> = PutBackPeekMore::new(0..6);
+        iter.peek();
+        iter.put_back(Some(9));
+        assert!(iter.eq([9, 0, 1, 2, 3, 4, 5]));
+    }
+
+    #[test]
+    fn map_source_drops_retained() {
+        let mut iter: PutBackPeekMore<_, 4> = PutBackPeekMore::new(0..10);
+        iter.seek_relative(2).unwrap();
+        let mut iter = iter.take(1);
+        assert_eq!(iter.rewind(), Err(()));
+        assert!(iter.peek.iter().all(Option::is_none));
+        assert!(iter.eq([2]));
+    }
 }
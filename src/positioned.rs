@@ -0,0 +1,221 @@
+use crate::PutBackPeekMore;
+
+///A smart iterator like `PutBackPeekMore` that keeps track of where the next value is in the stream, for lexers that report positions.
+///Putting back a value moves the position back by one, so a value has the same position every time it is consumed.
+pub struct Positioned<Iter, const BUFSIZE: usize>
+where
+    Iter: Iterator,
+{
+    /// The wrapped iterator, which holds the buffer and counts the consumed values.
+    pub(crate) inner: PutBackPeekMore<Iter, BUFSIZE>,
+    /// Decides which values end a line.
+    pub(crate) is_line_break: fn(&Iter::Item) -> bool,
+    /// How many line breaks were consumed minus how many were put back.
+    pub(crate) line: usize,
+    /// The positions where the last `BUFSIZE` lines start, indexed by the line number modulo `BUFSIZE`.
+    pub(crate) line_starts: [usize; BUFSIZE],
+    /// Where the line before the ones in the structs `line_starts` field starts, for when `BUFSIZE` line breaks were put back.
+    /// No line further back is ever needed, since at most `BUFSIZE` values can be put back.
+    /// Only reaching a new line replaces it, because a line that is read again after putting back starts at the same position.
+    pub(crate) evicted_start: usize,
+}
+
+impl<Iter, const BUFSIZE: usize> Positioned<Iter, BUFSIZE>
+where
+    Iter: Iterator,
+{
+    ///Create a new iterator that only tracks the position, so every value is on line 0.
+    pub fn new(iter: Iter) -> Self {
+        Self::with_line_breaks(iter, |_| false)
+    }
+
+    ///Create a new iterator that also tracks lines and columns, starting a new line after every value that satisfies `is_line_break`.
+    pub fn with_line_breaks(iter: Iter, is_line_break: fn(&Iter::Item) -> bool) -> Self {
+        Self {
+            inner: PutBackPeekMore::new(iter),
+            is_line_break,
+            line: 0,
+            line_starts: [0; BUFSIZE],
+            evicted_start: 0,
+        }
+    }
+
+    ///Returns how many values came before the next value, counting from the start of the stream, like `PutBackPeekMore::stream_position`.
+    pub fn stream_position(&self) -> usize {
+        self.inner.stream_position()
+    }
+
+    ///Returns the line of the next value, starting at 0.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    ///Returns how many values come before the next value on its line, starting at 0.
+    pub fn column(&self) -> usize {
+        let position = self.stream_position();
+        let start = self.line_starts[self.line % BUFSIZE];
+        if start > position {
+            position.wrapping_sub(self.evicted_start)
+        } else {
+            position - start
+        }
+    }
+
+    ///Look at the next value of the iterator without consuming it.
+    pub fn peek(&mut self) -> Option<&Iter::Item> {
+        self.inner.peek_checked()
+    }
+
+    ///Look at the next `amount` values of the iterator without consuming it.
+    ///
+    ///# Panics
+    ///Panics if `amount` is larger than `BUFSIZE`.
+    pub fn peek_value(&mut self, amount: usize) -> &[Option<Iter::Item>] {
+        self.inner.peek_value(amount)
+    }
+
+    ///Change the next consumed value of the iterator, moving the position back by one.
+    ///
+    ///# Panics
+    ///Panics if the buffer is full, like `PutBackPeekMore::put_back`.
+    pub fn put_back(&mut self, val: Iter::Item) {
+        let is_line_break = (self.is_line_break)(&val);
+        self.inner.put_back(Some(val));
+        if is_line_break {
+            self.line = self.line.saturating_sub(1);
+        }
+    }
+}
+
+impl<Iter, const BUFSIZE: usize> Positioned<Iter, BUFSIZE>
+where
+    Iter: Iterator<Item = char>,
+{
+    ///Create a new iterator over characters that starts a new line after every `'\n'`.
+    pub fn from_chars(iter: Iter) -> Self {
+        Self::with_line_breaks(iter, |c| *c == '\n')
+    }
+}
+
+impl<Iter, const BUFSIZE: usize> core::fmt::Debug for Positioned<Iter, BUFSIZE>
+where
+    Iter: Iterator,
+    Iter::Item: core::fmt::Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Positioned")
+            .field("inner", &self.inner)
+            .field("line", &self.line)
+            .field("line_starts", &self.line_starts)
+            .field("evicted_start", &self.evicted_start)
+            .finish()
+    }
+}
+
+impl<Iter, const BUFSIZE: usize> Iterator for Positioned<Iter, BUFSIZE>
+where
+    Iter: Iterator,
+{
+    type Item = Iter::Item;
+    ///Consume the iterator.
+    fn next(&mut self) -> Option<Self::Item> {
+        let out = self.inner.next();
+        if out.as_ref().is_some_and(self.is_line_break) {
+            self.line += 1;
+            let start = self.stream_position();
+            let old = core::mem::replace(&mut self.line_starts[self.line % BUFSIZE], start);
+            if old < start {
+                self.evicted_start = old;
+            }
+        }
+        out
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Positioned;
+
+    #[test]
+    fn stream_position() {
+        let mut iter: Positioned<_, 3> = Positioned::new(0..10);
+        assert_eq!(iter.stream_position(), 0);
+        iter.next();
+        iter.next();
+        assert_eq!(iter.stream_position(), 2);
+        iter.put_back(1);
+        assert_eq!(iter.stream_position(), 1);
+        assert_eq!(iter.peek(), Some(&1));
+        assert_eq!(iter.line(), 0);
+    }
+
+    #[test]
+    fn line_and_column() {
+        let mut iter: Positioned<_, 4> = Positioned::from_chars("ab\ncd\n\ne".chars());
+        let mut seen = [(0, 0); 8];
+        for slot in &mut seen {
+            *slot = (iter.line(), iter.column());
+            iter.next();
+        }
+        assert_eq!(
+            seen,
+            [
+                (0, 0),
+                (0, 1),
+                (0, 2),
+                (1, 0),
+                (1, 1),
+                (1, 2),
+                (2, 0),
+                (3, 0)
+            ]
+        );
+        iter.put_back('e');
+        iter.put_back('\n');
+        assert_eq!((iter.line(), iter.column()), (2, 0));
+        iter.put_back('\n');
+        iter.put_back('d');
+        assert_eq!((iter.line(), iter.column()), (1, 1));
+        assert!(iter.eq("d\n\ne".chars()));
+    }
+
+    #[test]
+    fn column_after_put_back_of_every_slot() {
+        let mut iter: Positioned<_, 2> = Positioned::from_chars("ab\n\n\nc".chars());
+        for _ in 0..5 {
+            iter.next();
+        }
+        assert_eq!((iter.line(), iter.column()), (3, 0));
+        iter.put_back('\n');
+        assert_eq!((iter.line(), iter.column()), (2, 0));
+        iter.put_back('\n');
+        assert_eq!((iter.line(), iter.column()), (1, 0));
+        iter.next();
+        assert_eq!((iter.line(), iter.column()), (2, 0));
+        iter.put_back('\n');
+        assert_eq!((iter.line(), iter.column()), (1, 0));
+        assert!(iter.eq("\n\nc".chars()));
+
+        let mut iter: Positioned<_, 2> = Positioned::from_chars("a\nb\nc\nd".chars());
+        for _ in 0..6 {
+            iter.next();
+        }
+        iter.put_back('\n');
+        iter.put_back('c');
+        assert_eq!((iter.line(), iter.column()), (2, 0));
+    }
+
+    #[test]
+    fn position_at_end() {
+        let mut iter: Positioned<_, 2> = Positioned::from_chars("ab".chars());
+        for _ in 0..4 {
+            iter.next();
+        }
+        assert_eq!(iter.stream_position(), 2);
+        assert_eq!((iter.line(), iter.column()), (0, 2));
+    }
+}
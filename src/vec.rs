@@ -0,0 +1,187 @@
+use alloc::collections::VecDeque;
+
+///A smart iterator like `PutBackPeekMore` that lets you peek at any amount of values and put back as many as you want.
+///The buffer is a `VecDeque` that grows on demand, so there is no `BUFSIZE` to pick.
+pub struct PutBackPeekMoreVec<Iter>
+where
+    Iter: Iterator,
+{
+    /// The iterator to consume.
+    pub(crate) iter: Iter,
+    /// A buffer containing the peeked and put back values, in the order they will be consumed.
+    pub(crate) peek: VecDeque<Iter::Item>,
+}
+
+impl<Iter> PutBackPeekMoreVec<Iter>
+where
+    Iter: Iterator,
+{
+    ///Create a new iterator.
+    pub fn new(iter: Iter) -> Self {
+        Self {
+            iter,
+            peek: VecDeque::new(),
+        }
+    }
+
+    ///Create a new iterator whose buffer can hold `cap` values before it has to reallocate.
+    pub fn with_capacity(iter: Iter, cap: usize) -> Self {
+        Self {
+            iter,
+            peek: VecDeque::with_capacity(cap),
+        }
+    }
+
+    ///Returns how many values the buffer can hold before it has to reallocate.
+    pub fn capacity(&self) -> usize {
+        self.peek.capacity()
+    }
+
+    ///Look at the next value of the iterator without consuming it.
+    pub fn peek(&mut self) -> Option<&Iter::Item> {
+        self.demand(1);
+        self.peek.front()
+    }
+
+    ///Look at the next `amount` values of the iterator without consuming it.
+    ///Fewer values are returned if the iterator ends first.
+    pub fn peek_value(&mut self, amount: usize) -> &[Iter::Item] {
+        self.demand(amount);
+        let len = amount.min(self.peek.len());
+        &self.peek.make_contiguous()[..len]
+    }
+
+    ///Look at every remaining value of the iterator without consuming it.
+    ///This reads the whole underlying iterator into the buffer, so only use it on iterators that are small enough to keep in memory.
+    pub fn peek_all(&mut self) -> &[Iter::Item] {
+        self.peek.extend(&mut self.iter);
+        self.peek.make_contiguous()
+    }
+
+    ///Expose the values that are already buffered as one slice, without reading from the underlying iterator.
+    ///Use `peek_value` or `peek_all` first to decide how much of the iterator should be buffered.
+    pub fn make_contiguous(&mut self) -> &[Iter::Item] {
+        self.peek.make_contiguous()
+    }
+
+    ///Tells the struct to read from the structs `iter` field until `val` values are buffered or the iterator ends.
+    pub(crate) fn demand(&mut self, val: usize) {
+        let missing = val.saturating_sub(self.peek.len());
+        self.peek.extend(self.iter.by_ref().take(missing));
+    }
+
+    ///Change the next consumed value of the iterator.
+    pub fn put_back(&mut self, val: Iter::Item) {
+        self.peek.push_front(val);
+    }
+
+    ///Put back several values at once, so the next values are `items` in order, followed by the values that were next before.
+    ///The buffer grows to fit them, so this never fails.
+    pub fn put_back_n(&mut self, items: impl IntoIterator<Item = Iter::Item>) {
+        let before = self.peek.len();
+        self.peek.extend(items);
+        self.peek.rotate_right(self.peek.len() - before);
+    }
+}
+
+impl<Iter> core::fmt::Debug for PutBackPeekMoreVec<Iter>
+where
+    Iter: Iterator,
+    Iter::Item: core::fmt::Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("PutBackPeekMoreVec")
+            .field("iter", &"...")
+            .field("peek", &self.peek)
+            .finish()
+    }
+}
+
+impl<Iter> Iterator for PutBackPeekMoreVec<Iter>
+where
+    Iter: Iterator,
+{
+    type Item = Iter::Item;
+    ///Consume the iterator.
+    fn next(&mut self) -> Option<Self::Item> {
+        self.peek.pop_front().or_else(|| self.iter.next())
+    }
+
+    ///Counts the buffered values on top of the size hint of the structs `iter` field.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let buffered = self.peek.len();
+        let (lower, upper) = self.iter.size_hint();
+        (
+            lower.saturating_add(buffered),
+            upper.and_then(|upper| upper.checked_add(buffered)),
+        )
+    }
+}
+
+impl<Iter> ExactSizeIterator for PutBackPeekMoreVec<Iter> where Iter: ExactSizeIterator {}
+
+#[cfg(test)]
+mod tests {
+    use crate::PutBackPeekMoreVec;
+
+    #[test]
+    fn test_peek() {
+        let mut iter = PutBackPeekMoreVec::new(0..10);
+        assert_eq!(iter.peek(), Some(&0));
+        assert_eq!(iter.next(), Some(0));
+        assert_eq!(iter.peek(), Some(&1));
+    }
+
+    #[test]
+    fn test_peek_value() {
+        let mut iter = PutBackPeekMoreVec::new(0..3);
+        assert_eq!(iter.peek_value(2), &[0, 1]);
+        assert_eq!(iter.peek_value(5), &[0, 1, 2]);
+    }
+
+    #[test]
+    fn test_put_back() {
+        let mut iter = PutBackPeekMoreVec::new(0..10);
+        iter.put_back(10);
+        iter.put_back(11);
+        assert_eq!(iter.peek_value(3), &[11, 10, 0]);
+        assert_eq!(iter.next(), Some(11));
+    }
+
+    #[test]
+    fn with_capacity() {
+        let mut iter = PutBackPeekMoreVec::with_capacity(0..100, 16);
+        let cap = iter.capacity();
+        assert!(cap >= 16);
+        assert_eq!(iter.peek_value(16).len(), 16);
+        assert_eq!(iter.capacity(), cap);
+    }
+
+    #[test]
+    fn peek_all() {
+        let mut iter = PutBackPeekMoreVec::new(0..5);
+        assert_eq!(iter.peek_all(), &[0, 1, 2, 3, 4]);
+        assert_eq!(iter.next(), Some(0));
+        assert_eq!(iter.peek_all(), &[1, 2, 3, 4]);
+        assert!(iter.eq(1..5));
+    }
+
+    #[test]
+    fn make_contiguous() {
+        let mut iter = PutBackPeekMoreVec::new(0..10);
+        assert_eq!(iter.make_contiguous(), &[] as &[i32]);
+        iter.peek_value(3);
+        iter.put_back(10);
+        assert_eq!(iter.make_contiguous(), &[10, 0, 1, 2]);
+        assert_eq!(iter.next(), Some(10));
+    }
+
+    #[test]
+    fn put_back_n() {
+        let mut iter = PutBackPeekMoreVec::new(0..10);
+        iter.peek_value(3);
+        iter.put_back_n(10..20);
+        assert_eq!(iter.size_hint(), (20, Some(20)));
+        assert!(iter.eq((10..20).chain(0..10)));
+    }
+}